@@ -2,15 +2,38 @@
 //! It contains critical functions like get_input(), update_state(), and update_screen()
 //! that are crucual for running the game
 use rand::Rng;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Editor, Helper};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
-use std::fs::read_to_string;
+use std::fs::{read_to_string, write};
 use std::io::stdout;
 use std::io::{self, Write};
 use std::path::Path;
+use std::str::FromStr;
 use std::thread;
 use std::time::Duration;
 
+/// Where the game is saved to and loaded from
+pub const SAVE_FILE_LOCATION: &str = "./savegame.ron";
+/// Where the translations of the game are kept, one `<language>.ron` file per language
+pub const LOCALE_DIR: &str = "./locales";
+
+/// Translates a message with the world's catalog and fills in its placeholders
+macro_rules! tr {
+    ($world:expr, $text:expr $(, $arg:expr)* $(,)?) => {
+        $world
+            .catalog
+            .translate($text, &[$(&$arg as &dyn fmt::Display),*])
+    };
+}
+
 // Indices of all the objects in the game
 pub const LOC_FOREST: usize = 0;
 pub const LOC_DUNGEONS: usize = 1;
@@ -23,6 +46,59 @@ pub const LOC_BEAR: usize = 7;
 pub const LOC_TROLL: usize = 8;
 pub const LOC_BANDITS: usize = 9;
 
+/// The most health the player can have
+pub const MAX_HEALTH: u64 = 100;
+
+/// Reminder of the commands that work during a fight
+pub const FIGHT_HINT: &str =
+    "Hint: Use the following commands when attacking: 'use <weapon name>' or 'inventory' or 'run'\n";
+/// Reminder of the commands that work during a conversation
+pub const TALK_HINT: &str =
+    "Hint: Enter the number of your reply, or 'bye' to leave the conversation\n";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(transparent)]
+/// Health of a creature, or how much a consumable heals. It never goes below zero or
+/// above the maximum it is healed to, whatever the damage.
+pub struct Health(pub u64);
+
+impl Health {
+    /// Returns the health as a number
+    pub fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Checks if there is no health left
+    pub fn is_dead(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Takes away damage, stopping at zero. Returns the damage actually taken.
+    pub fn damage(&mut self, amount: u64) -> u64 {
+        let taken = amount.min(self.0);
+        self.0 -= taken;
+        taken
+    }
+
+    /// Adds health up to a maximum. Returns how much was healed.
+    pub fn heal(&mut self, amount: u64, max: u64) -> u64 {
+        let healed = self.0.saturating_add(amount).min(max.max(self.0)) - self.0;
+        self.0 += healed;
+        healed
+    }
+
+    /// Returns the health scaled by a percentage, like for the difficulty
+    pub fn scaled(self, percent: u64) -> Health {
+        Health(self.0.saturating_mul(percent) / 100)
+    }
+}
+
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 ///Distance enum containing all the distance prompts
 #[derive(PartialOrd, Ord, PartialEq, Eq, Debug)]
 pub enum Distance {
@@ -36,17 +112,42 @@ pub enum Distance {
 }
 
 /// Command enum containing all the command prompts
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     Drop(String),
     Get(String),
     Attack(String),
     Look(String),
     Go(String),
+    Travel(String),
+    Talk(String),
+    Buy(String),
+    Sell(String),
+    Consume(String),
+    Equip(String),
+    Repair(String),
+    Combine(String),
+    Craft(String),
+    Save(String),
+    Load(String),
+    Use(String),
+    Choose(usize),
     Unknown(String),
+    Run,
+    Leave,
     Inventory,
+    Quests,
+    Recipes,
+    List,
+    Status,
+    Stats,
     Quit,
-    Help,
+    Help(String),
     Map,
+    /// Repeats the last command
+    Again,
+    /// Several commands carried out one after the other
+    Chain(Vec<Command>),
 }
 
 /// Get input from the user
@@ -57,17 +158,382 @@ impl fmt::Display for Command {
             Command::Get(_) => write!(f, "get"),
             Command::Attack(_) => write!(f, "attack"),
             Command::Go(_) => write!(f, "go"),
+            Command::Travel(_) => write!(f, "travel"),
+            Command::Talk(_) => write!(f, "talk"),
+            Command::Buy(_) => write!(f, "buy"),
+            Command::Sell(_) => write!(f, "sell"),
+            Command::Consume(_) => write!(f, "eat"),
+            Command::List => write!(f, "list"),
+            Command::Equip(_) => write!(f, "equip"),
+            Command::Repair(_) => write!(f, "repair"),
+            Command::Combine(_) => write!(f, "combine"),
+            Command::Craft(_) => write!(f, "craft"),
+            Command::Save(_) => write!(f, "save"),
+            Command::Load(_) => write!(f, "load"),
+            Command::Use(_) => write!(f, "use"),
+            Command::Run => write!(f, "run"),
+            Command::Choose(_) => write!(f, "choose"),
+            Command::Leave => write!(f, "bye"),
+            Command::Status => write!(f, "status"),
+            Command::Stats => write!(f, "stats"),
             Command::Inventory => write!(f, "inventory"),
+            Command::Quests => write!(f, "quests"),
+            Command::Recipes => write!(f, "recipes"),
             Command::Look(_) => write!(f, "look"),
             Command::Quit => write!(f, "quit"),
             Command::Unknown(_) => write!(f, "unknown"),
-            Command::Help => write!(f, "help"),
+            Command::Help(_) => write!(f, "help"),
             Command::Map => write!(f, "map"),
+            Command::Again => write!(f, "again"),
+            Command::Chain(commands) => {
+                let verbs: Vec<String> = commands.iter().map(ToString::to_string).collect();
+                write!(f, "{}", verbs.join(", "))
+            }
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Command {
+    /// Whether carrying out the command while exploring counts as a turn. Commands that
+    /// only show information, belong to a fight or a conversation, or stand for other
+    /// commands don't.
+    pub fn takes_turn(&self) -> bool {
+        !matches!(
+            self,
+            Command::Help(_)
+                | Command::Look(_)
+                | Command::Inventory
+                | Command::Map
+                | Command::Quests
+                | Command::Recipes
+                | Command::List
+                | Command::Status
+                | Command::Stats
+                | Command::Quit
+                | Command::Unknown(_)
+                | Command::Use(_)
+                | Command::Run
+                | Command::Choose(_)
+                | Command::Leave
+                | Command::Again
+                | Command::Chain(_)
+        )
+    }
+}
+
+/// How to use a command, shown by `help`
+pub struct CommandHelp {
+    /// The words that start the command. The first one is the main one.
+    pub verbs: &'static [&'static str],
+    pub usage: &'static str,
+    pub description: &'static str,
+    pub examples: &'static [&'static str],
+    pub exploring: bool,
+    pub fighting: bool,
+    pub talking: bool,
+}
+
+impl CommandHelp {
+    /// Checks if the command can be used in a mode
+    pub fn available(&self, mode: &Mode) -> bool {
+        match mode {
+            Mode::Exploring => self.exploring,
+            Mode::Fighting(_) => self.fighting,
+            Mode::Talking { .. } => self.talking,
+        }
+    }
+}
+
+/// Builds the help of a command that only works while exploring
+const fn explore_help(
+    verbs: &'static [&'static str],
+    usage: &'static str,
+    description: &'static str,
+    examples: &'static [&'static str],
+) -> CommandHelp {
+    CommandHelp {
+        verbs,
+        usage,
+        description,
+        examples,
+        exploring: true,
+        fighting: false,
+        talking: false,
+    }
+}
+
+/// Every command, in the order `help` lists them
+pub const COMMANDS: &[CommandHelp] = &[
+    explore_help(
+        &["look", "examine"],
+        "look [<name>]",
+        "Describe where you are, or take a closer look at something",
+        &["look", "look at sword", "examine bear"],
+    ),
+    explore_help(
+        &["go"],
+        "go <direction or place>",
+        "Walk to a neighbouring place",
+        &["go north", "go tavern"],
+    ),
+    explore_help(
+        &["go to", "travel"],
+        "go to <place>",
+        "Walk the shortest way to a place you have been to",
+        &["go to village", "travel forest"],
+    ),
+    explore_help(
+        &["attack"],
+        "attack <enemy>",
+        "Start a fight, or shoot at an enemy nearby with a bow",
+        &["attack bear"],
+    ),
+    CommandHelp {
+        verbs: &["use"],
+        usage: "use <weapon>",
+        description: "Strike the enemy you are fighting",
+        examples: &["use sword"],
+        exploring: false,
+        fighting: true,
+        talking: false,
+    },
+    CommandHelp {
+        verbs: &["run", "flee"],
+        usage: "run",
+        description: "Run away from the fight",
+        examples: &[],
+        exploring: false,
+        fighting: true,
+        talking: false,
+    },
+    CommandHelp {
+        verbs: &["<number>"],
+        usage: "<number>",
+        description: "Choose one of the numbered replies",
+        examples: &["1"],
+        exploring: false,
+        fighting: false,
+        talking: true,
+    },
+    CommandHelp {
+        verbs: &["bye", "leave"],
+        usage: "bye",
+        description: "Leave the conversation",
+        examples: &[],
+        exploring: false,
+        fighting: false,
+        talking: true,
+    },
+    explore_help(
+        &["get", "pick up"],
+        "get <item>",
+        "Pick up an item. Healing items are used straight away",
+        &["get sword", "get apple"],
+    ),
+    explore_help(
+        &["drop"],
+        "drop <item>",
+        "Put down an item you are holding",
+        &["drop bones"],
+    ),
+    explore_help(
+        &["talk"],
+        "talk to <name>",
+        "Start a conversation",
+        &["talk to innkeeper"],
+    ),
+    explore_help(
+        &["eat", "drink"],
+        "eat <item>",
+        "Eat or drink something you are holding",
+        &["eat apple", "drink potion"],
+    ),
+    explore_help(
+        &["list"],
+        "list",
+        "See what the merchant here has for sale",
+        &[],
+    ),
+    explore_help(
+        &["buy"],
+        "buy <item>",
+        "Buy an item from the merchant here",
+        &["buy potion"],
+    ),
+    explore_help(
+        &["sell"],
+        "sell <item>",
+        "Sell an item to the merchant here",
+        &["sell bones"],
+    ),
+    CommandHelp {
+        verbs: &["inventory"],
+        usage: "inventory",
+        description: "List what you are carrying",
+        examples: &[],
+        exploring: true,
+        fighting: true,
+        talking: false,
+    },
+    explore_help(
+        &["equip"],
+        "equip <weapon>",
+        "Ready a weapon",
+        &["equip bow"],
+    ),
+    explore_help(
+        &["repair", "fix"],
+        "repair <item>",
+        "Repair a worn item at a forge or with a repair kit",
+        &["repair sword"],
+    ),
+    explore_help(
+        &["combine"],
+        "combine <item> with <item>",
+        "Try to make something out of two items",
+        &["combine bones with spear"],
+    ),
+    explore_help(
+        &["craft"],
+        "craft <item>",
+        "Make something from a recipe you know",
+        &["craft bone spear"],
+    ),
+    explore_help(&["recipes"], "recipes", "List the recipes you know", &[]),
+    explore_help(&["quests", "journal"], "quests", "List your quests", &[]),
+    explore_help(
+        &["map"],
+        "map",
+        "Draw a map of the places you have been to",
+        &[],
+    ),
+    explore_help(
+        &["save"],
+        "save [<file>]",
+        "Save the game",
+        &["save", "save mygame.ron"],
+    ),
+    explore_help(
+        &["load"],
+        "load [<file>]",
+        "Load a saved game",
+        &["load", "load mygame.ron"],
+    ),
+    CommandHelp {
+        verbs: &["status", "hud"],
+        usage: "status",
+        description: "Turn the status bar on or off",
+        examples: &[],
+        exploring: true,
+        fighting: true,
+        talking: true,
+    },
+    CommandHelp {
+        verbs: &["again", "g"],
+        usage: "again",
+        description: "Do the last command again",
+        examples: &[],
+        exploring: true,
+        fighting: true,
+        talking: true,
+    },
+    CommandHelp {
+        verbs: &["stats", "score"],
+        usage: "stats",
+        description: "Show your score so far",
+        examples: &[],
+        exploring: true,
+        fighting: true,
+        talking: true,
+    },
+    CommandHelp {
+        verbs: &["help"],
+        usage: "help [<command>]",
+        description: "List the commands, or explain one",
+        examples: &["help", "help attack"],
+        exploring: true,
+        fighting: true,
+        talking: true,
+    },
+    CommandHelp {
+        verbs: &["quit"],
+        usage: "quit",
+        description: "Stop playing",
+        examples: &[],
+        exploring: true,
+        fighting: true,
+        talking: true,
+    },
+];
+
+/// How well a typed name fits an object's label, from worst to best
+#[derive(PartialOrd, Ord, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LabelMatch {
+    None,
+    Typo,
+    Prefix,
+    Word,
+    Exact,
+}
+
+/// Shortest typed word that may be the start of a longer one
+const MIN_PREFIX: usize = 3;
+
+/// Splits a name or description into lowercase words without punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// How many typos a word of this length may have and still be recognised. Short words
+/// are too close to each other, like "west" and "east", to allow any.
+fn typo_allowance(length: usize) -> usize {
+    match length {
+        0..=4 => 0,
+        5..=6 => 1,
+        _ => 2,
+    }
+}
+
+/// How well one typed word fits one word of a name
+fn word_match(typed: &str, word: &str) -> LabelMatch {
+    let length = typed.chars().count();
+    if typed == word {
+        LabelMatch::Word
+    } else if length >= MIN_PREFIX && word.starts_with(typed) {
+        LabelMatch::Prefix
+    } else if edit_distance(typed, word) <= typo_allowance(length.min(word.chars().count())) {
+        LabelMatch::Typo
+    } else {
+        LabelMatch::None
+    }
+}
+
+/// Counts the letters that have to be added, removed, changed or swapped with their
+/// neighbour to turn one word into another
+pub fn edit_distance(from: &str, to: &str) -> usize {
+    let from: Vec<char> = from.chars().collect();
+    let to: Vec<char> = to.chars().collect();
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=to.len()).collect();
+    for i in 0..from.len() {
+        let mut current = vec![i + 1];
+        for j in 0..to.len() {
+            let substitution = previous[j] + usize::from(from[i] != to[j]);
+            let mut distance = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            if i > 0 && j > 0 && from[i] == to[j - 1] && from[i - 1] == to[j] {
+                distance = distance.min(before[j - 1] + 1);
+            }
+            current.push(distance);
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[to.len()]
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 /// The object struct
 pub struct Object {
     pub label: Vec<String>,
@@ -76,9 +542,334 @@ pub struct Object {
     pub destination: Option<usize>,
     pub item: Option<bool>,
     pub enemy: bool,
-    pub health: Option<u64>,
+    pub health: Option<Health>,
     pub attack: Option<u64>,
     pub consumable: Option<bool>,
+    #[serde(default)]
+    pub npc: bool,
+    #[serde(default)]
+    pub dialogue: Option<Vec<DialogueNode>>,
+    #[serde(default)]
+    pub gold: Option<u64>,
+    #[serde(default)]
+    pub price: Option<u64>,
+    #[serde(default)]
+    pub merchant: bool,
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub visited: bool,
+    #[serde(default)]
+    pub discovered: bool,
+    #[serde(default)]
+    pub accuracy: Option<u64>,
+    #[serde(default)]
+    pub crit_chance: Option<u64>,
+    #[serde(default)]
+    pub crit_multiplier: Option<u64>,
+    #[serde(default)]
+    pub defence: Option<u64>,
+    #[serde(default)]
+    pub dodge: Option<u64>,
+    #[serde(default)]
+    pub effect: Option<EffectSpec>,
+    #[serde(default)]
+    pub ranged: bool,
+    #[serde(default)]
+    pub ammo: Option<usize>,
+    #[serde(default)]
+    pub quantity: Option<u64>,
+    #[serde(default)]
+    pub durability: Option<u64>,
+    #[serde(default)]
+    pub max_durability: Option<u64>,
+    #[serde(default)]
+    pub broken: Option<String>,
+    #[serde(default)]
+    pub repairs: bool,
+    #[serde(default)]
+    pub repair: Option<u64>,
+    #[serde(default)]
+    pub loot: Vec<String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub despawned: bool,
+    #[serde(default)]
+    pub max_health: Option<u64>,
+    #[serde(default)]
+    pub regen: Option<u64>,
+    #[serde(default)]
+    pub respawn: Option<u64>,
+    #[serde(default)]
+    pub died_at: Option<u64>,
+    /// Set once the enemy has been killed, so a respawned one gives no score or loot
+    #[serde(default)]
+    pub looted: bool,
+    #[serde(default)]
+    pub on_flee: FleeBehaviour,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// What happens to an enemy's health when the player runs from a fight
+pub enum FleeBehaviour {
+    /// The enemy is back to full health
+    FullHeal,
+    /// The enemy gets back a percentage of the health it lost
+    PartialHeal(u64),
+    /// The enemy keeps its wounds
+    #[default]
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// A single line of an NPC's dialogue tree. Node 0 is where every conversation starts.
+pub struct DialogueNode {
+    pub text: String,
+    pub options: Vec<DialogueOption>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// A reply the player can choose. `next` of None ends the conversation.
+pub struct DialogueOption {
+    pub text: String,
+    #[serde(default)]
+    pub next: Option<usize>,
+    #[serde(default)]
+    pub effects: Vec<DialogueEffect>,
+    #[serde(default)]
+    pub requires_flag: Option<String>,
+    #[serde(default)]
+    pub unless_flag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// Things that happen when the player picks a dialogue option
+pub enum DialogueEffect {
+    SetFlag(String),
+    GiveItem(usize),
+    StartQuest(String),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// A quest from the world file. Its objectives have to be completed in order.
+pub struct Quest {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    #[serde(default)]
+    pub reward: QuestReward,
+    #[serde(default)]
+    pub status: QuestStatus,
+    #[serde(default)]
+    pub progress: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// A single step of a quest. Each one holds the index of the object it is about.
+pub enum Objective {
+    Reach(usize),
+    Obtain(usize),
+    Defeat(usize),
+    TalkTo(usize),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// Whether a quest has been started or finished
+pub enum QuestStatus {
+    #[default]
+    Inactive,
+    Active,
+    Completed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// What the player receives for completing a quest
+pub struct QuestReward {
+    #[serde(default)]
+    pub items: Vec<usize>,
+    #[serde(default)]
+    pub health: Option<u64>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// A way of making a new item out of others. The ingredients are used up and the
+/// result is spawned from a template.
+pub struct Recipe {
+    pub ingredients: Vec<usize>,
+    pub result: String,
+    #[serde(default)]
+    pub known: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// How hard the game is
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Custom(DifficultySettings),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// What a difficulty changes. All values are percentages.
+pub struct DifficultySettings {
+    /// Scales the damage enemies do
+    pub enemy_damage: u64,
+    /// Scales the health enemies start with
+    pub enemy_health: u64,
+    /// Scales how much healing items heal
+    pub healing: u64,
+    /// Chance of dodging an enemy attack, on top of the enemy missing
+    pub dodge: u64,
+}
+
+impl Difficulty {
+    pub fn settings(&self) -> DifficultySettings {
+        match self {
+            Difficulty::Easy => DifficultySettings {
+                enemy_damage: 50,
+                enemy_health: 75,
+                healing: 150,
+                dodge: 20,
+            },
+            Difficulty::Normal => DifficultySettings {
+                enemy_damage: 100,
+                enemy_health: 100,
+                healing: 100,
+                dodge: 0,
+            },
+            Difficulty::Hard => DifficultySettings {
+                enemy_damage: 150,
+                enemy_health: 150,
+                healing: 50,
+                dodge: 0,
+            },
+            Difficulty::Custom(settings) => *settings,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+            Difficulty::Custom(settings) => write!(
+                f,
+                "Custom (enemy damage {}%, enemy health {}%, healing {}%, dodge {}%)",
+                settings.enemy_damage, settings.enemy_health, settings.healing, settings.dodge
+            ),
+        }
+    }
+}
+
+/// Reads a difficulty like "hard", or "custom:150,100,50,10" for enemy damage,
+/// enemy health, healing and dodge. The error is a catalog message, with a placeholder
+/// for what was typed.
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.trim().to_lowercase();
+        match input.as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" | "" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => {
+                let values: Vec<u64> = input
+                    .strip_prefix("custom:")
+                    .ok_or("Unknown difficulty '{}'".to_string())?
+                    .split(',')
+                    .map(|value| value.trim().parse::<u64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| "Custom difficulty values must be whole numbers".to_string())?;
+                match values[..] {
+                    [enemy_damage, enemy_health, healing, dodge] if dodge <= 100 => {
+                        Ok(Difficulty::Custom(DifficultySettings {
+                            enemy_damage,
+                            enemy_health,
+                            healing,
+                            dodge,
+                        }))
+                    }
+                    _ => Err("A custom difficulty needs four values: enemy damage, enemy health, healing and dodge (at most 100)".to_string()),
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// The kinds of lasting effects on creatures
+pub enum EffectKind {
+    Poison,
+    Bleeding,
+    Stun,
+    Strength,
+    Regeneration,
+}
+
+impl fmt::Display for EffectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EffectKind::Poison => write!(f, "poisoned"),
+            EffectKind::Bleeding => write!(f, "bleeding"),
+            EffectKind::Stun => write!(f, "stunned"),
+            EffectKind::Strength => write!(f, "strengthened"),
+            EffectKind::Regeneration => write!(f, "regenerating"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// An effect a weapon, enemy or consumable puts on a creature. `chance` is in percent,
+/// for weapons and enemies it is rolled on every hit.
+pub struct EffectSpec {
+    pub kind: EffectKind,
+    pub turns: u64,
+    #[serde(default)]
+    pub power: u64,
+    #[serde(default)]
+    pub chance: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// An effect currently on a creature
+pub struct StatusEffect {
+    pub target: usize,
+    pub kind: EffectKind,
+    pub turns_left: u64,
+    pub power: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+/// Running totals of the player's game, used for the score
+pub struct Stats {
+    pub enemies_defeated: u64,
+    pub damage_taken: u64,
+    pub items_collected: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// One finished game in the high score table
+pub struct HighScore {
+    pub name: String,
+    pub world_file: String,
+    pub score: u64,
+    pub turns: u64,
+    pub won: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+/// The high score table kept between games
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
 }
 
 /// Handles any ambiguous directions
@@ -89,10 +880,202 @@ pub enum AmbiguousOption<T> {
     Ambiguous,
 }
 
+/// The player-facing text and command words of one language. The English text in the code
+/// is the key, so anything without a translation is shown in English.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Catalog {
+    #[serde(default)]
+    pub language: String,
+    /// English messages and their translations. Placeholders are written `{}`, or `{0}`,
+    /// `{1}`, ... when a translation needs them in a different order.
+    #[serde(default)]
+    pub messages: HashMap<String, String>,
+    /// Words and phrases the player can type, and the English words they stand for
+    #[serde(default)]
+    pub vocabulary: HashMap<String, String>,
+}
+
+/// English words that join commands or name directions. They are translated wherever the
+/// player types them, unlike command words.
+const FREE_WORDS: &[&str] = &["with", "and", "then", "north", "south", "east", "west"];
+
+impl Catalog {
+    /// Reads a catalog from a RON file
+    pub fn read_from_file(locale_file: &str) -> Result<Catalog, std::io::Error> {
+        let contents = read_to_string(locale_file)?;
+        ron::from_str(&contents).map_err(|ron_err| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, ron_err.to_string())
+        })
+    }
+
+    /// Loads the catalog for a language from the locale directory. English needs no file.
+    pub fn load(language: &str) -> Result<Catalog, std::io::Error> {
+        if language == "en" {
+            return Ok(Catalog::default());
+        }
+        Catalog::read_from_file(&format!("{}/{}.ron", LOCALE_DIR, language))
+    }
+
+    /// Translates a message and fills in its placeholders
+    pub fn translate(&self, text: &str, args: &[&dyn fmt::Display]) -> String {
+        let template = self.messages.get(text).map_or(text, String::as_str);
+        let mut result = String::new();
+        let mut next = 0;
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            result += &rest[..open];
+            rest = &rest[open + 1..];
+            match rest.find('}') {
+                Some(close) if rest[..close].chars().all(|c| c.is_ascii_digit()) => {
+                    let index = rest[..close].parse().unwrap_or_else(|_| {
+                        next += 1;
+                        next - 1
+                    });
+                    if let Some(arg) = args.get(index) {
+                        result += &arg.to_string();
+                    }
+                    rest = &rest[close + 1..];
+                }
+                _ => result.push('{'),
+            }
+        }
+        result + rest
+    }
+
+    /// Translates a single word or name, like a status effect or a difficulty
+    pub fn word(&self, word: impl ToString) -> String {
+        self.translate(&word.to_string(), &[])
+    }
+
+    /// Rewrites what the player typed with the English command words, so `parse` understands
+    /// it. Longer phrases are matched first, and words not in the vocabulary are kept.
+    /// Command words are only rewritten at the start of a command, so they can't change
+    /// the name of an object; the words in `FREE_WORDS` are rewritten anywhere.
+    pub fn to_english(&self, input: &str) -> String {
+        // Commas and semicolons separate commands, so they can't be part of a word
+        let input = input.to_lowercase().replace(',', " , ").replace(';', " ; ");
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let longest = self
+            .vocabulary
+            .keys()
+            .map(|phrase| phrase.split_whitespace().count())
+            .max()
+            .unwrap_or(0);
+        let mut result: Vec<String> = Vec::new();
+        let mut pos = 0;
+        let mut command_start = true;
+        'words: while pos < words.len() {
+            for length in (1..=longest.min(words.len() - pos)).rev() {
+                match self.vocabulary.get(&words[pos..pos + length].join(" ")) {
+                    Some(english) if command_start || FREE_WORDS.contains(&english.as_str()) => {
+                        // "help" is followed by the command word to explain
+                        command_start = english == "then" || english == "help";
+                        result.push(english.clone());
+                        pos += length;
+                        continue 'words;
+                    }
+                    _ => {}
+                }
+            }
+            command_start = matches!(words[pos], "," | ";");
+            result.push(words[pos].to_string());
+            pos += 1;
+        }
+        result.join(" ")
+    }
+}
+
+/// Something that happened while carrying out a command. `World::render` turns these into text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Plain text that has no structure of its own
+    Message(String),
+    /// The command could not be carried out, and the text says why
+    Failed(String),
+    /// The player arrived in a location
+    Moved { location: usize, first_visit: bool },
+    /// The player walked through a location on the way somewhere else
+    PassedThrough { passage: usize, location: usize },
+    /// The player took an item from a location or a container
+    ItemPickedUp { item: usize, from: usize },
+    /// The player put an item down where they are
+    ItemDropped(usize),
+    /// The player ate or drank something and healed
+    ItemConsumed { item: usize, health: u64 },
+    /// A creature was hit in combat
+    Damaged {
+        target: usize,
+        amount: u64,
+        health_left: u64,
+    },
+    /// The player started a fight
+    CombatStarted(usize),
+    /// The player killed an enemy
+    EnemyKilled(usize),
+    /// The player ran away from a fight
+    Fled(usize),
+    /// The player was killed
+    PlayerDied,
+    /// The player stopped playing
+    Quit,
+    /// An NPC says something and the player can reply with one of the options
+    Dialogue {
+        npc: usize,
+        text: String,
+        options: Vec<String>,
+    },
+    /// The player stopped talking to an NPC
+    ConversationEnded(usize),
+}
+
+/// What the player is busy with. Fights and conversations only accept their own commands.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Exploring,
+    Fighting(usize),
+    Talking {
+        npc: usize,
+        node: usize,
+    },
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 /// The world struct
 pub struct World {
     pub objects: Vec<Object>,
+    #[serde(default)]
+    pub flags: Vec<String>,
+    #[serde(default)]
+    pub quests: Vec<Quest>,
+    #[serde(default)]
+    pub turn: u64,
+    #[serde(default)]
+    pub equipped: Option<usize>,
+    #[serde(default)]
+    pub show_status: bool,
+    #[serde(default)]
+    pub stats: Stats,
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    #[serde(default)]
+    pub effects: Vec<StatusEffect>,
+    #[serde(default)]
+    pub recipes: Vec<Recipe>,
+    #[serde(default)]
+    pub templates: HashMap<String, Object>,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(skip)]
+    events: Vec<Event>,
+    /// Whether the command being carried out didn't work
+    #[serde(skip)]
+    failed: bool,
+    /// What `again` repeats
+    #[serde(skip)]
+    last_command: Option<Command>,
+    #[serde(skip)]
+    pub catalog: Catalog,
 }
 
 /// The game struct
@@ -110,6 +1093,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Dungeons".to_string()],
@@ -121,6 +1105,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Cave".to_string()],
@@ -132,6 +1117,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Tavern".to_string()],
@@ -145,6 +1131,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Village".to_string()],
@@ -158,6 +1145,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Stronghold".to_string()],
@@ -171,6 +1159,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Player".to_string()],
@@ -179,9 +1168,10 @@ impl World {
                     destination: None,
                     item: Some(false),
                     enemy: false,
-                    health: Some(100),
+                    health: Some(Health(100)),
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Bear".to_string()],
@@ -190,9 +1180,10 @@ impl World {
                     destination: None,
                     item: Some(false),
                     enemy: true,
-                    health: Some(100),
+                    health: Some(Health(100)),
                     attack: Some(20),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Troll (enemy)".to_string()],
@@ -201,9 +1192,10 @@ impl World {
                     destination: None,
                     item: Some(false),
                     enemy: true,
-                    health: Some(100),
+                    health: Some(Health(100)),
                     attack: Some(20),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Bandits (enemy)".to_string()],
@@ -212,9 +1204,10 @@ impl World {
                     destination: None,
                     item: Some(false),
                     enemy: true,
-                    health: Some(100),
+                    health: Some(Health(100)),
                     attack: Some(30),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Sword".to_string()],
@@ -226,6 +1219,7 @@ impl World {
                     health: None,
                     attack: Some(20),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Bow".to_string()],
@@ -237,6 +1231,7 @@ impl World {
                     health: None,
                     attack: Some(10),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Bones".to_string()],
@@ -248,6 +1243,7 @@ impl World {
                     health: None,
                     attack: Some(5),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Spear".to_string()],
@@ -259,6 +1255,7 @@ impl World {
                     health: None,
                     attack: Some(25),
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Apple".to_string()],
@@ -267,9 +1264,10 @@ impl World {
                     destination: None,
                     item: Some(true),
                     enemy: false,
-                    health: Some(10),
+                    health: Some(Health(10)),
                     attack: None,
                     consumable: Some(true),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["Potion".to_string()],
@@ -278,9 +1276,10 @@ impl World {
                     destination: None,
                     item: Some(true),
                     enemy: false,
-                    health: Some(20),
+                    health: Some(Health(20)),
                     attack: None,
                     consumable: Some(true),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["North".to_string()],
@@ -293,6 +1292,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["South".to_string()],
@@ -304,6 +1304,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["East".to_string()],
@@ -315,6 +1316,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["West".to_string()],
@@ -326,6 +1328,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["East".to_string()],
@@ -337,6 +1340,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["North".to_string()],
@@ -348,6 +1352,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["South".to_string()],
@@ -359,6 +1364,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["West".to_string()],
@@ -370,6 +1376,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["North".to_string()],
@@ -381,6 +1388,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["South".to_string()],
@@ -392,6 +1400,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["West".to_string(), "East".to_string(), "South".to_string()],
@@ -404,6 +1413,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["West".to_string(), "East".to_string(), "North".to_string()],
@@ -416,6 +1426,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["North".to_string(), "".to_string()],
@@ -427,6 +1438,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["East".to_string(),"West".to_string()],
@@ -438,6 +1450,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["East".to_string(), "South".to_string()],
@@ -451,6 +1464,7 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
                 Object {
                     label: vec!["East".to_string(), "North".to_string(), "West".to_string()],
@@ -462,8 +1476,24 @@ impl World {
                     health: None,
                     attack: None,
                     consumable: Some(false),
+                    ..Default::default()
                 },
             ],
+            flags: Vec::new(),
+            quests: Vec::new(),
+            turn: 0,
+            equipped: None,
+            show_status: true,
+            stats: Stats::default(),
+            difficulty: Difficulty::Normal,
+            effects: Vec::new(),
+            recipes: Vec::new(),
+            templates: HashMap::new(),
+            mode: Mode::Exploring,
+            events: Vec::new(),
+            failed: false,
+            last_command: None,
+            catalog: Catalog::default(),
         }
     }
 
@@ -478,26 +1508,105 @@ impl World {
                     ron::from_str(&game_file_data);
 
                 match deserialized_data {
-                    Ok(deserialized_ron) => Ok(deserialized_ron),
-                    Err(de_err_str) => Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
-                        de_err_str.to_string(),
-                    )),
+                    Ok(deserialized_ron) => match deserialized_ron.check_dialogue() {
+                        Ok(()) => Ok(deserialized_ron),
+                        Err(check_err) => Err(std::io::Error::other(check_err)),
+                    },
+                    Err(de_err_str) => Err(std::io::Error::other(de_err_str.to_string())),
                 }
             }
             Err(file_err) => Err(file_err),
         }
     }
 
+    /// Checks that every dialogue option leads to a node the NPC actually has
+    pub fn check_dialogue(&self) -> Result<(), String> {
+        for object in &self.objects {
+            let Some(nodes) = &object.dialogue else {
+                continue;
+            };
+            for (node_index, node) in nodes.iter().enumerate() {
+                for option in &node.options {
+                    if let Some(next) = option.next.filter(|&next| next >= nodes.len()) {
+                        return Err(format!(
+                            "Dialogue of {} goes from node {} to node {}, but there are only {} nodes",
+                            object.label[0],
+                            node_index,
+                            next,
+                            nodes.len()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the whole world, including the player's progress, to a file
+    pub fn save_to_file(&self, save_file: &str) -> Result<(), std::io::Error> {
+        let serialized_data = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|ser_err| std::io::Error::other(ser_err.to_string()))?;
+        write(Path::new(save_file), serialized_data)
+    }
+
+    /// Saves the game
+    pub fn do_save(&self, noun: &str) -> String {
+        let save_file = if noun.is_empty() {
+            SAVE_FILE_LOCATION
+        } else {
+            noun
+        };
+        match self.save_to_file(save_file) {
+            Ok(()) => tr!(self, "Game saved to {}.\n", save_file),
+            Err(file_err) => tr!(self, "Could not save the game: {}\n", file_err),
+        }
+    }
+
+    /// Loads a saved game, replacing the current one
+    pub fn do_load(&mut self, noun: &str) -> String {
+        let save_file = if noun.is_empty() {
+            SAVE_FILE_LOCATION
+        } else {
+            noun
+        };
+        match World::read_from_file(save_file) {
+            Ok(world) => {
+                let show_status = self.show_status;
+                let catalog = std::mem::take(&mut self.catalog);
+                *self = world;
+                self.show_status = show_status;
+                self.catalog = catalog;
+                tr!(self, "Game loaded from {}.\n", save_file) + &self.do_look("")
+            }
+            Err(file_err) => tr!(self, "Could not load the game: {}\n", file_err),
+        }
+    }
+
+    /// Changes the difficulty, scaling the health of the enemies to match
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        let old = self.difficulty.settings().enemy_health.max(1);
+        let new = difficulty.settings().enemy_health;
+        for object in self.objects.iter_mut().filter(|object| object.enemy) {
+            object.health = object.health.map(|health| match health.value() {
+                0 => health,
+                value => Health((value.saturating_mul(new) / old).max(1)),
+            });
+        }
+        self.difficulty = difficulty;
+    }
+
+    /// Checks if the player has died or has defeated all enemies, without announcing it
+    pub fn is_finished(&self) -> bool {
+        let dead = |index: usize| self.objects[index].health.is_some_and(Health::is_dead);
+        dead(LOC_PLAYER) || (dead(LOC_BEAR) && dead(LOC_TROLL) && dead(LOC_BANDITS))
+    }
+
     /// Check of the game is over
     pub fn game_over(&self) -> bool {
-        if self.objects[LOC_PLAYER].health == Some(0) {
+        if self.objects[LOC_PLAYER].health.is_some_and(Health::is_dead) {
             true
-        } else if self.objects[LOC_BEAR].health == Some(0)
-            && self.objects[LOC_TROLL].health == Some(0)
-            && self.objects[LOC_BANDITS].health == Some(0)
-        {
-            println!("You have defeated all enemies! You win!");
+        } else if self.is_finished() {
+            println!("{}", tr!(self, "You have defeated all enemies! You win!"));
             true
         } else {
             false
@@ -516,7 +1625,7 @@ impl World {
     /// Check if the object has a label
     fn object_with_label(&self, object: &Object, noun: &str) -> bool {
         let mut result = false;
-        for (_, label) in object.label.iter().enumerate() {
+        for label in object.label.iter() {
             if label.to_lowercase() == noun.to_lowercase() {
                 result = true;
                 break;
@@ -525,23 +1634,79 @@ impl World {
         result
     }
 
-    /// Get the index of the object
+    /// How closely the name the player typed fits an object. Every word typed has to fit a
+    /// word of the label or description, and at least one has to fit the label.
+    fn label_match(&self, object: &Object, noun: &str) -> LabelMatch {
+        if self.object_with_label(object, noun) {
+            return LabelMatch::Exact;
+        }
+        let label_words = words(&object.label.join(" "));
+        let all_words = [label_words.clone(), words(&object.description)].concat();
+        let typed = words(noun);
+        let best_fit = |word: &String, candidates: &[String]| {
+            candidates
+                .iter()
+                .map(|candidate| word_match(word, candidate))
+                .max()
+                .unwrap_or(LabelMatch::None)
+        };
+        if typed.is_empty()
+            || !typed
+                .iter()
+                .any(|word| best_fit(word, &label_words) > LabelMatch::None)
+        {
+            return LabelMatch::None;
+        }
+        let quality = typed
+            .iter()
+            .map(|word| best_fit(word, &all_words))
+            .min()
+            .unwrap_or(LabelMatch::None);
+        // A typo in a direction could quietly send the player the wrong way
+        if quality == LabelMatch::Typo && object.destination.is_some() {
+            LabelMatch::None
+        } else {
+            quality
+        }
+    }
+
+    /// Get the index of the object. Only the closest fitting objects count, so an exact
+    /// name is never ambiguous with a typo of another one.
     pub fn object_index(
         &self,
         noun: &str,
         from: Option<usize>,
         max_distance: Distance,
+    ) -> AmbiguousOption<usize> {
+        self.best_match(
+            noun,
+            (0..self.objects.len())
+                .filter(|&position| self.get_distance(from, Some(position)) <= max_distance),
+        )
+    }
+
+    /// Picks the candidate whose name fits what the player typed best
+    fn best_match(
+        &self,
+        noun: &str,
+        candidates: impl Iterator<Item = usize>,
     ) -> AmbiguousOption<usize> {
         let mut result: AmbiguousOption<usize> = AmbiguousOption::None;
-        for (position, object) in self.objects.iter().enumerate() {
-            if self.object_with_label(object, noun)
-                && self.get_distance(from, Some(position)) <= max_distance
-            {
-                if result == AmbiguousOption::None {
-                    result = AmbiguousOption::Some(position);
-                } else {
-                    result = AmbiguousOption::Ambiguous;
-                }
+        let mut best = LabelMatch::None;
+        for position in candidates {
+            let object = &self.objects[position];
+            if object.despawned {
+                continue;
+            }
+            let quality = self.label_match(object, noun);
+            if quality == LabelMatch::None || quality < best {
+                continue;
+            }
+            if quality > best {
+                best = quality;
+                result = AmbiguousOption::Some(position);
+            } else {
+                result = AmbiguousOption::Ambiguous;
             }
         }
         result
@@ -555,15 +1720,15 @@ impl World {
         match (obj_over_there, obj_not_here) {
             // Return none if not a valid command
             (AmbiguousOption::None, AmbiguousOption::None) => {
-                ("Invalid command!!".to_string(), None)
+                (tr!(self, "Invalid command!!"), None)
             }
             (AmbiguousOption::None, AmbiguousOption::Some(_)) => {
-                (format!("You don't see any '{}' here.\n", noun), None)
+                (tr!(self, "You don't see any '{}' here.\n", noun), None)
             }
             // Ambiguous object name
             (AmbiguousOption::Ambiguous, _)
             | (AmbiguousOption::None, AmbiguousOption::Ambiguous) => (
-                format!("Please be more specific about which {} you mean. Try typing out the location.\n", noun),
+                tr!(self, "Please be more specific about which {} you mean. Try typing out the location.\n", noun),
                 None,
             ),
             (AmbiguousOption::Some(index), _) => (String::new(), Some(index)),
@@ -580,202 +1745,1690 @@ impl World {
                 && object.label.len() == 1
             {
                 if count == 0 {
-                    result += "\nYou see:\n";
+                    result += &tr!(self, "\nYou see:\n");
                 }
                 count += 1;
-                result += &format!("{}\n", object.description);
+                match object.quantity {
+                    Some(quantity) => {
+                        result += &tr!(self, "{} ({} left)\n", object.description, quantity)
+                    }
+                    None => result += &format!("{}\n", object.description),
+                }
             }
         }
         (result, count)
     }
 
-    /// Updates state of the game
-    pub fn update_state(&mut self, command: &Command) -> String {
+    /// Updates state of the game and returns what happened
+    pub fn update_state(&mut self, command: &Command) -> Vec<Event> {
         match command {
+            Command::Again => match self.last_command.clone() {
+                Some(last) => self.update_state(&last),
+                None => vec![Event::Failed(tr!(self, "There is nothing to do again.\n"))],
+            },
+            Command::Chain(commands) => {
+                let commands = self.resolve_again(commands);
+                let events = self.carry_out_chain(&commands);
+                // A chain still holding an "again" would repeat itself forever
+                if !commands.contains(&Command::Again) {
+                    self.last_command = Some(Command::Chain(commands));
+                }
+                events
+            }
+            _ => {
+                self.last_command = Some(command.clone());
+                self.carry_out(command)
+            }
+        }
+    }
+
+    /// Replaces every "again" in a chain with the command before it, or with the last
+    /// command for the first one. An "again" with nothing before it is kept, and fails.
+    fn resolve_again(&self, commands: &[Command]) -> Vec<Command> {
+        let mut previous = self.last_command.clone();
+        commands
+            .iter()
+            .map(|command| {
+                let resolved = match (command, &previous) {
+                    (Command::Again, Some(last)) => last.clone(),
+                    _ => command.clone(),
+                };
+                previous = Some(resolved.clone());
+                resolved
+            })
+            .collect()
+    }
+
+    /// Carries out commands one at a time until one of them fails, the player quits or the
+    /// game is over
+    fn carry_out_chain(&mut self, commands: &[Command]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (position, command) in commands.iter().enumerate() {
+            let was_fighting = matches!(self.mode, Mode::Fighting(_));
+            let step = self.update_state(command);
+            let stop = self.is_finished()
+                || step
+                    .iter()
+                    .any(|event| matches!(event, Event::Failed(_) | Event::Quit));
+            events.extend(step);
+            // Once the fight is over the blows still to come have nothing to hit
+            let fight_over = was_fighting
+                && !matches!(self.mode, Mode::Fighting(_))
+                && matches!(commands.get(position + 1), Some(Command::Use(_)));
+            if stop || fight_over {
+                break;
+            }
+        }
+        events
+    }
+
+    /// Carries out a single command
+    fn carry_out(&mut self, command: &Command) -> Vec<Event> {
+        self.events.clear();
+        self.failed = false;
+        let output = match self.mode {
+            Mode::Exploring => self.explore(command),
+            Mode::Fighting(enemy) => self.fight(enemy, command),
+            Mode::Talking { npc, node } => self.converse(npc, node, command),
+        };
+        self.report(output);
+        std::mem::take(&mut self.events)
+    }
+
+    /// Runs a command and renders what happened as text
+    pub fn play(&mut self, command: &Command) -> String {
+        let events = self.update_state(command);
+        self.render(&events)
+    }
+
+    /// Records an event for the command being carried out
+    fn emit(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Records some text for the command being carried out
+    fn say(&mut self, text: impl Into<String>) {
+        let text = text.into();
+        if !text.is_empty() {
+            self.emit(Event::Message(text));
+        }
+    }
+
+    /// Marks the command being carried out as failed and passes on the reason
+    fn refuse(&mut self, text: impl Into<String>) -> String {
+        self.failed = true;
+        text.into()
+    }
+
+    /// Records the text a command ended with, as a failure if the command didn't work
+    fn report(&mut self, text: String) {
+        if std::mem::take(&mut self.failed) {
+            self.emit(Event::Failed(text));
+        } else {
+            self.say(text);
+        }
+    }
+
+    /// Handles a command while the player is walking around
+    fn explore(&mut self, command: &Command) -> String {
+        if command.takes_turn() {
+            self.turn += 1;
+        }
+        // Fights tick effects every round themselves, and saving or loading leaves the
+        // world as it is
+        let ticks = command.takes_turn()
+            && !matches!(
+                command,
+                Command::Attack(_) | Command::Save(_) | Command::Load(_)
+            );
+        let output = match command {
             Command::Look(noun) => self.do_look(noun),
             Command::Go(noun) => self.do_go(noun),
-            Command::Quit => "Quitting.\nThank you for playing!".to_string(),
+            Command::Travel(noun) => self.do_travel(noun),
+            Command::Quit => {
+                self.emit(Event::Quit);
+                String::new()
+            }
             Command::Attack(noun) => self.do_attack(noun),
             Command::Drop(noun) => self.do_drop(noun),
             Command::Get(noun) => self.do_get(noun),
+            Command::Talk(noun) => self.do_talk(noun),
+            Command::Buy(noun) => self.do_buy(noun),
+            Command::Sell(noun) => self.do_sell(noun),
+            Command::Consume(noun) => self.do_eat(noun),
+            Command::List => self.do_list(),
+            Command::Equip(noun) => self.do_equip(noun),
+            Command::Repair(noun) => self.do_repair(noun),
+            Command::Stats => self.display_summary(),
+            Command::Save(noun) => self.do_save(noun),
+            Command::Load(noun) => self.do_load(noun),
+            Command::Status => {
+                self.show_status = !self.show_status;
+                if self.show_status {
+                    tr!(self, "Status bar on.\n")
+                } else {
+                    tr!(self, "Status bar off.\n")
+                }
+            }
             Command::Inventory => self.do_inventory(),
-            Command::Help => self.display_help(),
-            Command::Map => self.display_locations(),
-            Command::Unknown(_) => {
-                let invalid_msg = String::from("Invalid command!!\n");
-                let help = self.display_help();
-                invalid_msg + help.as_str()
+            Command::Quests => self.do_quests(),
+            Command::Recipes => self.do_recipes(),
+            Command::Combine(noun) => self.do_combine(noun),
+            Command::Craft(noun) => self.do_craft(noun),
+            Command::Help(topic) => self.display_help(topic),
+            Command::Map => self.display_map(),
+            Command::Use(_) | Command::Run => {
+                self.refuse(tr!(self, "You are not fighting anyone.\n"))
+            }
+            Command::Choose(_) | Command::Leave => {
+                self.refuse(tr!(self, "You are not talking to anyone.\n"))
             }
+            Command::Unknown(input) => self.refuse(self.unknown_command(input)),
+            // update_state takes these apart before they get here
+            Command::Again | Command::Chain(_) => String::new(),
+        };
+        if !ticks {
+            return output;
         }
+        self.report(output);
+        self.pass_turn()
     }
 
-    /// Function to perform the attack while attacking an enemy
-    pub fn do_use(&mut self, msg: &str, mut obj_health: u64, obj_index: usize) -> u64 {
-        let mut split_input = msg.split_whitespace();
-        let noun = split_input.nth(1).unwrap_or_default().to_string();
-        let (output, obj_opt) = self.object_visible(&noun);
-        match obj_opt {
-            Some(weapon_index) if !self.objects[weapon_index].enemy => {
-                if let Some(attack_pwr) = self.objects[weapon_index].attack {
-                    if let Some(enemy_pwr) = self.objects[obj_index].attack {
-                        obj_health -= attack_pwr;
-                        self.type_writer_effect(&format!(
-                            "You attacked the {}.\nEnemy health: {}",
-                            self.objects[obj_index].label[0], obj_health
-                        ));
-                        if obj_health == 0 {
-                            self.objects[obj_index].health = Some(0);
-                            return obj_health;
-                        }
-                        self.type_writer_effect(&format!(
-                            "\n\nThe {} attacks",
-                            self.objects[obj_index].label[0]
-                        ));
-                        // random attack
-                        let mut rng = rand::thread_rng();
-                        let attack: u64 = rng.gen_range(0..enemy_pwr);
-                        if attack == 0 {
-                            self.type_writer_effect("\nYou dodged the attack");
-                            obj_health
-                        } else {
-                            self.type_writer_effect("\nYou got hit");
-                            self.objects[LOC_PLAYER].health = Some(
-                                self.objects[LOC_PLAYER]
-                                    .health
-                                    .map(|h| h - attack)
-                                    .unwrap_or(0),
-                            );
-                            self.type_writer_effect(&format!(
-                                "\nYour health: {}",
-                                self.objects[LOC_PLAYER].health.unwrap_or(0)
-                            ));
-                            obj_health
-                        }
-                    } else {
-                        obj_health
-                    }
-                } else {
-                    self.type_writer_effect("That is not a weapon!!");
-                    println!("\nHint: Use the following commands: use <weapon name> or run");
-                    obj_health
-                }
-            }
-            Some(_) => {
-                self.type_writer_effect("That is not a weapon!!");
-                println!("\nHint: Use the following commands: use <weapon name> or run");
-                obj_health
+    /// Lets a turn pass outside of a fight: status effects tick and enemies recover
+    fn pass_turn(&mut self) -> String {
+        if !self.effects.is_empty() {
+            let log = self.tick_effects();
+            self.say(log.trim_start().to_string() + "\n");
+        }
+        self.enemies_recover()
+    }
+
+    /// Handles a command in the middle of a fight
+    fn fight(&mut self, enemy: usize, command: &Command) -> String {
+        match command {
+            Command::Use(noun) => self.do_use(noun),
+            Command::Run => self.do_run(enemy),
+            Command::Inventory => self.do_inventory(),
+            Command::Help(_) | Command::Status | Command::Stats | Command::Quit => {
+                self.explore(command)
             }
-            None => {
-                self.type_writer_effect(&output);
-                obj_health
+            Command::Unknown(input) => self.refuse(self.unknown_command(input)),
+            _ => self.refuse(tr!(self, FIGHT_HINT)),
+        }
+    }
+
+    /// Handles a command in the middle of a conversation
+    fn converse(&mut self, npc: usize, node: usize, command: &Command) -> String {
+        match command {
+            Command::Choose(choice) => self.do_choose(npc, node, *choice),
+            Command::Leave => self.end_conversation(npc),
+            Command::Help(_) | Command::Status | Command::Stats | Command::Quit => {
+                self.explore(command)
             }
+            Command::Unknown(input) => self.refuse(self.unknown_command(input)),
+            _ => self.refuse(tr!(self, TALK_HINT)),
         }
     }
 
-    /// Function to attack an enemy
-    pub fn do_attack(&mut self, noun: &String) -> String {
-        let (output, obj_opt) = self.object_visible(noun);
+    /// Turns events into the text shown to the player
+    pub fn render(&self, events: &[Event]) -> String {
+        events
+            .iter()
+            .map(|event| self.render_event(event))
+            .collect()
+    }
 
-        match obj_opt {
-            Some(obj_index) => {
-                if self.objects[obj_index].enemy {
-                    let mut obj_health: u64 =
-                        obj_opt.and_then(|a| self.objects[a].health).unwrap_or(0);
-                    if obj_health == 0 {
-                        return format!(
-                            "The {} is already dead.\n",
-                            self.objects[obj_index].label[0]
-                        );
+    /// Turns a single event into text
+    pub fn render_event(&self, event: &Event) -> String {
+        let label = |index: usize| &self.objects[index].label[0];
+        match event {
+            Event::Message(text) | Event::Failed(text) => text.clone(),
+            Event::Moved { .. } => tr!(self, "OK.\n"),
+            Event::PassedThrough { passage, location } => tr!(
+                self,
+                "You go {} and pass through the {}.\n",
+                label(*passage).to_lowercase(),
+                label(*location)
+            ),
+            Event::ItemPickedUp { item, from } => {
+                if Some(*from) == self.objects[LOC_PLAYER].location {
+                    tr!(self, "You pick up the {}.\n", label(*item))
+                } else {
+                    tr!(self, "You got {} from {}.\n", label(*item), label(*from))
+                }
+            }
+            Event::ItemDropped(item) => tr!(self, "You have dropped {}.\n", label(*item)),
+            Event::ItemConsumed { health, .. } => tr!(
+                self,
+                "You have consumed the item. Your health has increased to {}\n",
+                health
+            ),
+            Event::Damaged {
+                target,
+                health_left,
+                ..
+            } => {
+                if *target == LOC_PLAYER {
+                    tr!(self, "\nYour health: {}", health_left)
+                } else {
+                    tr!(self, "Enemy health: {}", health_left)
+                }
+            }
+            Event::CombatStarted(enemy) => tr!(
+                self,
+                "\nYou are attacking the {}.\n\n{}",
+                label(*enemy),
+                tr!(self, FIGHT_HINT)
+            ),
+            Event::EnemyKilled(enemy) => tr!(self, "\nYou killed the {}.\n", label(*enemy)),
+            Event::Fled(enemy) => tr!(self, "You ran away from the {}.\n", label(*enemy)),
+            Event::PlayerDied => tr!(self, "\nYou died"),
+            Event::Quit => tr!(self, "Quitting.\nThank you for playing!"),
+            Event::Dialogue { npc, text, options } => {
+                let mut result = format!("\n{}: \"{}\"\n", label(*npc), text);
+                for (number, option) in options.iter().enumerate() {
+                    result += &format!("  {}. {}\n", number + 1, option);
+                }
+                result
+            }
+            Event::ConversationEnded(npc) => {
+                tr!(self, "\nYou stop talking to the {}.\n", label(*npc))
+            }
+        }
+    }
+
+    /// Player strikes the enemy they are fighting with a weapon
+    pub fn do_use(&mut self, noun: &String) -> String {
+        let enemy = match self.mode {
+            Mode::Fighting(enemy) => enemy,
+            _ => return self.refuse(tr!(self, "You are not fighting anyone.\n")),
+        };
+        let (output, obj_opt) = self.object_visible(noun);
+        match obj_opt {
+            Some(weapon_index)
+                if !self.objects[weapon_index].enemy
+                    && self.objects[weapon_index].attack.is_some() =>
+            {
+                if let Err(output) = self.take_ammo(weapon_index) {
+                    return self.refuse(output);
+                }
+                self.equipped = Some(weapon_index);
+                if self.objects[enemy].attack.is_some() {
+                    self.combat_round(weapon_index, enemy);
+                }
+                self.end_of_round(enemy)
+            }
+            Some(_) => self.refuse(tr!(
+                self,
+                "That is not a weapon!!\nHint: Use the following commands: use <weapon name> or run\n"
+            )),
+            None => self.refuse(output),
+        }
+    }
+
+    /// Ends the fight if someone died in the last round
+    fn end_of_round(&mut self, enemy: usize) -> String {
+        if self.health(enemy).is_dead() {
+            self.mode = Mode::Exploring;
+            self.clear_effects(enemy);
+            self.defeat_enemy(enemy)
+        } else if self.health(LOC_PLAYER).is_dead() {
+            self.mode = Mode::Exploring;
+            self.emit(Event::PlayerDied);
+            String::new()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Player runs away from the enemy they are fighting
+    pub fn do_run(&mut self, enemy: usize) -> String {
+        self.mode = Mode::Exploring;
+        self.clear_effects(enemy);
+        self.apply_flee(enemy);
+        self.emit(Event::Fled(enemy));
+        String::new()
+    }
+
+    /// One round of a fight: the player strikes, the enemy strikes back and the
+    /// status effects tick. Every blow is applied to the enemy straight away.
+    fn combat_round(&mut self, weapon_index: usize, obj_index: usize) {
+        let enemy_pwr = self.objects[obj_index].attack.unwrap_or(0);
+        let mut rng = rand::thread_rng();
+
+        if self.is_stunned(LOC_PLAYER) {
+            self.say(tr!(self, "You are stunned and can't attack!\n"));
+        } else {
+            let (log, damage) = self.resolve_player_attack(
+                weapon_index,
+                obj_index,
+                rng.gen_range(0..100),
+                rng.gen_range(0..100),
+            );
+            self.say(log);
+            self.hit(obj_index, damage);
+            let log = self.wear(weapon_index);
+            self.say(log);
+            if damage > 0 {
+                if let Some(effect) = self.objects[weapon_index].effect.clone() {
+                    if rng.gen_range(0..100) < effect.chance.unwrap_or(100) {
+                        let log = self.apply_effect(obj_index, &effect);
+                        self.say(log);
                     }
-                    self.type_writer_effect(&format!(
-                        "\nYou are attacking the {}.\n",
-                        self.objects[obj_index].label[0]
-                    ));
-                    println!("\nHint: Use the following commands when attacking: 'use <weapon name>' or 'inventory' or 'run'");
-                    loop {
-                        if self.objects[LOC_PLAYER].health.unwrap_or(0) == 0 {
-                            return "\nYou died".to_string();
-                        }
-                        print!("\n> ");
-                        io::stdout().flush().unwrap();
-
-                        let mut command = String::new();
-                        io::stdin()
-                            .read_line(&mut command)
-                            .expect("Failed to read input");
-                        if command.contains("run") {
-                            break;
-                        } else if command.contains("inventory") {
-                            let list_objects = self.do_inventory();
-                            self.type_writer_effect(&list_objects);
-                            continue;
-                        } else if command.contains("use") {
-                            obj_health = self.do_use(&command, obj_health, obj_index);
-                            if obj_health == 0 {
-                                break;
-                            }
+                }
+            }
+        }
+        if self.health(obj_index).is_dead() {
+            return;
+        }
+
+        if self.is_stunned(obj_index) {
+            self.say(tr!(
+                self,
+                "\n\nThe {} is stunned and can't attack",
+                self.objects[obj_index].label[0]
+            ));
+        } else {
+            self.say(tr!(
+                self,
+                "\n\nThe {} attacks",
+                self.objects[obj_index].label[0]
+            ));
+            // random attack
+            let (log, damage) = self.resolve_enemy_attack(
+                obj_index,
+                rng.gen_range(0..100),
+                rng.gen_range(0..100),
+                rng.gen_range(0..enemy_pwr.max(1)),
+            );
+            self.say(log);
+            if damage > 0 {
+                self.damage_player(damage);
+                let log = self.wear_armour();
+                self.say(log);
+                self.emit(Event::Damaged {
+                    target: LOC_PLAYER,
+                    amount: damage,
+                    health_left: self.health(LOC_PLAYER).value(),
+                });
+                if let Some(effect) = self.objects[obj_index].effect.clone() {
+                    if rng.gen_range(0..100) < effect.chance.unwrap_or(100) {
+                        let log = self.apply_effect(LOC_PLAYER, &effect);
+                        self.say(log);
+                    }
+                }
+            }
+        }
+
+        let log = self.tick_effects();
+        self.say(log);
+    }
+
+    /// Deals a blow to an enemy and records how much health it has left. Misses count too.
+    fn hit(&mut self, enemy: usize, damage: u64) {
+        let amount = self.damage(enemy, damage);
+        self.emit(Event::Damaged {
+            target: enemy,
+            amount,
+            health_left: self.health(enemy).value(),
+        });
+    }
+
+    /// Takes health away from the player
+    fn damage_player(&mut self, damage: u64) {
+        self.stats.damage_taken += self.damage(LOC_PLAYER, damage);
+    }
+
+    /// Returns the health of a creature. Something without health counts as dead.
+    pub fn health(&self, index: usize) -> Health {
+        self.objects[index].health.unwrap_or_default()
+    }
+
+    /// Takes health away from a creature. Returns the damage actually done.
+    pub fn damage(&mut self, index: usize, amount: u64) -> u64 {
+        let mut health = self.health(index);
+        let taken = health.damage(amount);
+        self.objects[index].health = Some(health);
+        taken
+    }
+
+    /// Gives health to a creature, up to a maximum. Returns how much it was healed.
+    pub fn heal(&mut self, index: usize, amount: u64, max: u64) -> u64 {
+        let mut health = self.health(index);
+        let healed = health.heal(amount, max);
+        self.objects[index].health = Some(health);
+        healed
+    }
+
+    /// Puts a status effect on a creature. An effect of the same kind is refreshed instead.
+    pub fn apply_effect(&mut self, target: usize, spec: &EffectSpec) -> String {
+        let existing = self
+            .effects
+            .iter_mut()
+            .find(|effect| effect.target == target && effect.kind == spec.kind);
+        match existing {
+            Some(effect) => {
+                effect.turns_left = effect.turns_left.max(spec.turns);
+                effect.power = effect.power.max(spec.power);
+            }
+            None => self.effects.push(StatusEffect {
+                target,
+                kind: spec.kind,
+                turns_left: spec.turns,
+                power: spec.power,
+            }),
+        }
+        if target == LOC_PLAYER {
+            tr!(
+                self,
+                "\nYou are {} for {} turns.",
+                self.catalog.word(spec.kind),
+                spec.turns
+            )
+        } else {
+            tr!(
+                self,
+                "\nThe {} is {} for {} turns.",
+                self.objects[target].label[0],
+                self.catalog.word(spec.kind),
+                spec.turns
+            )
+        }
+    }
+
+    /// Checks if a creature is stunned
+    pub fn is_stunned(&self, target: usize) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| effect.target == target && effect.kind == EffectKind::Stun)
+    }
+
+    /// Returns the total power of the effects of a kind on a creature
+    pub fn effect_power(&self, target: usize, kind: EffectKind) -> u64 {
+        self.effects
+            .iter()
+            .filter(|effect| effect.target == target && effect.kind == kind)
+            .map(|effect| effect.power)
+            .sum()
+    }
+
+    /// Removes all status effects from a creature
+    pub fn clear_effects(&mut self, target: usize) {
+        self.effects.retain(|effect| effect.target != target);
+    }
+
+    /// Lists the status effects on a creature, like "poisoned (2 turns)"
+    pub fn describe_effects(&self, target: usize) -> String {
+        self.effects
+            .iter()
+            .filter(|effect| effect.target == target)
+            .map(|effect| {
+                tr!(
+                    self,
+                    "{} ({} turns)",
+                    self.catalog.word(effect.kind),
+                    effect.turns_left
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Lets a turn pass for every status effect
+    pub fn tick_effects(&mut self) -> String {
+        let mut result = String::new();
+        // Worked out up front, as the effects are borrowed while they tick
+        let enemy_max: Vec<u64> = (0..self.objects.len())
+            .map(|pos| self.enemy_max_health(pos))
+            .collect();
+
+        for effect in self.effects.iter_mut() {
+            let name = self.objects[effect.target].label[0].clone();
+            let kind = self.catalog.word(effect.kind);
+            match (effect.kind, self.objects[effect.target].health.as_mut()) {
+                (EffectKind::Poison | EffectKind::Bleeding, Some(health)) => {
+                    let damage = health.damage(effect.power);
+                    let poison = effect.kind == EffectKind::Poison;
+                    if effect.target == LOC_PLAYER {
+                        self.stats.damage_taken += damage;
+                        let text = if poison {
+                            "\nYou take {} damage from poison."
                         } else {
-                            println!("\nHint: Use the following commands when attacking: 'use <weapon name>' or 'inventory' or 'run'");
-                        }
+                            "\nYou take {} damage from bleeding."
+                        };
+                        result += &tr!(self, text, damage);
+                    } else {
+                        let text = if poison {
+                            "\nThe {} takes {} damage from poison."
+                        } else {
+                            "\nThe {} takes {} damage from bleeding."
+                        };
+                        result += &tr!(self, text, name, damage);
                     }
-                    if obj_health == 0 {
-                        format!("\nYou killed the {}.\n", self.objects[obj_index].label[0])
+                }
+                (EffectKind::Regeneration, Some(health)) => {
+                    if effect.target == LOC_PLAYER {
+                        let healed = health.heal(effect.power, MAX_HEALTH);
+                        result += &tr!(self, "\nYou regenerate {} health.", healed);
+                    } else {
+                        let healed = health.heal(effect.power, enemy_max[effect.target]);
+                        result += &tr!(self, "\nThe {} regenerates {} health.", name, healed);
+                    }
+                }
+                _ => {}
+            }
+            effect.turns_left = effect.turns_left.saturating_sub(1);
+            if effect.turns_left == 0 {
+                if effect.target == LOC_PLAYER {
+                    result += &tr!(self, "\nYou are no longer {}.", kind);
+                } else {
+                    result += &tr!(self, "\nThe {} is no longer {}.", name, kind);
+                }
+            }
+        }
+        self.effects.retain(|effect| effect.turns_left > 0);
+        result
+    }
+
+    /// Works out one blow of the player against an enemy from two rolls between 0 and 99.
+    /// Returns the combat log and the damage done.
+    pub fn resolve_player_attack(
+        &self,
+        weapon_index: usize,
+        enemy_index: usize,
+        hit_roll: u64,
+        crit_roll: u64,
+    ) -> (String, u64) {
+        let weapon = &self.objects[weapon_index];
+        let enemy = &self.objects[enemy_index];
+        let accuracy = weapon.accuracy.unwrap_or(100);
+
+        if hit_roll >= accuracy {
+            return (
+                tr!(
+                    self,
+                    if weapon.ranged {
+                        "You fire the {} at the {} and miss. (rolled {}, needed under {})\n"
                     } else {
-                        format!(
-                            "You ran away from the {}.\n",
+                        "You swing the {} at the {} and miss. (rolled {}, needed under {})\n"
+                    },
+                    weapon.label[0],
+                    enemy.label[0],
+                    hit_roll,
+                    accuracy
+                ),
+                0,
+            );
+        }
+        let mut log = tr!(self, "You attacked the {}.\n", enemy.label[0]);
+        let mut damage = self.weapon_attack(weapon_index);
+        let strength = self.effect_power(LOC_PLAYER, EffectKind::Strength);
+        if strength > 0 {
+            damage = damage.saturating_add(strength);
+            log += &tr!(self, "Your strength adds {} damage.\n", strength);
+        }
+        if crit_roll < weapon.crit_chance.unwrap_or(0) {
+            damage = damage.saturating_mul(weapon.crit_multiplier.unwrap_or(200)) / 100;
+            log += &tr!(
+                self,
+                "Critical hit! (rolled {}, needed under {})\n",
+                crit_roll,
+                weapon.crit_chance.unwrap_or(0)
+            );
+        }
+        if let Some(defence) = enemy.defence {
+            damage = damage.saturating_sub(defence).max(1);
+            log += &tr!(
+                self,
+                "The {}'s defence absorbs {} damage.\n",
+                enemy.label[0],
+                defence
+            );
+        }
+        log += &tr!(self, "You deal {} damage.\n", damage);
+        (log, damage)
+    }
+
+    /// Returns the chance in percent that the player dodges an attack
+    pub fn player_dodge(&self) -> u64 {
+        let armour: u64 = (0..self.objects.len())
+            .filter(|&pos| self.is_containing(Some(LOC_PLAYER), Some(pos)))
+            .map(|pos| self.objects[pos].dodge.unwrap_or(0))
+            .sum();
+        self.difficulty
+            .settings()
+            .dodge
+            .saturating_add(self.objects[LOC_PLAYER].dodge.unwrap_or(0))
+            .saturating_add(armour)
+            .min(95)
+    }
+
+    /// Returns a weapon's attack. Badly worn weapons only do half their damage.
+    pub fn weapon_attack(&self, weapon_index: usize) -> u64 {
+        let weapon = &self.objects[weapon_index];
+        let attack = weapon.attack.unwrap_or(0);
+        match (weapon.durability, weapon.max_durability) {
+            (Some(durability), Some(max)) if durability * 4 < max => attack / 2,
+            _ => attack,
+        }
+    }
+
+    /// Wears down an item by one use. An item that is worn out breaks and is replaced by
+    /// its broken version, if it has one.
+    pub fn wear(&mut self, index: usize) -> String {
+        let durability = match self.objects[index].durability {
+            Some(durability) if durability > 0 => durability - 1,
+            _ => return String::new(),
+        };
+        self.objects[index].durability = Some(durability);
+        let label = self.objects[index].label[0].clone();
+        let max = self.objects[index].max_durability.unwrap_or(0);
+
+        if durability == 0 {
+            let location = self.objects[index].location;
+            self.despawn(index);
+            match self.objects[index]
+                .broken
+                .clone()
+                .and_then(|broken| self.spawn(&broken, location))
+            {
+                Some(broken) => {
+                    tr!(
+                        self,
+                        "\nYour {} breaks! All that is left is {}",
+                        label,
+                        self.objects[broken].description.to_lowercase()
+                    )
+                }
+                None => tr!(self, "\nYour {} breaks!", label),
+            }
+        } else if durability * 4 < max && (durability + 1) * 4 >= max {
+            tr!(self, "\nYour {} is badly worn.", label)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Wears down every piece of armour the player carries
+    fn wear_armour(&mut self) -> String {
+        let mut result = String::new();
+        for pos in 0..self.objects.len() {
+            if self.objects[pos].defence.is_some()
+                && self.is_containing(Some(LOC_PLAYER), Some(pos))
+            {
+                result += &self.wear(pos);
+            }
+        }
+        result
+    }
+
+    /// Checks if the player's location, or someone there, can repair gear
+    fn repairs_here(&self) -> bool {
+        (0..self.objects.len()).any(|pos| {
+            self.objects[pos].repairs
+                && matches!(
+                    self.get_distance(Some(LOC_PLAYER), Some(pos)),
+                    Distance::Location | Distance::Here
+                )
+        })
+    }
+
+    /// Player repairs a worn item, at a forge or with a repair item they carry
+    pub fn do_repair(&mut self, noun: &String) -> String {
+        let (output, object_index) = self.get_possession(
+            Some(LOC_PLAYER),
+            Command::Repair("repair".to_string()),
+            noun,
+        );
+        let index = match object_index {
+            Some(index) => index,
+            None => return self.refuse(output),
+        };
+        let label = self.objects[index].label[0].clone();
+        let (durability, max) = match (
+            self.objects[index].durability,
+            self.objects[index].max_durability,
+        ) {
+            (Some(durability), Some(max)) => (durability, max),
+            _ => return self.refuse(tr!(self, "The {} can't be repaired.\n", label)),
+        };
+        if durability >= max {
+            return self.refuse(tr!(self, "The {} is in perfect condition.\n", label));
+        }
+
+        if self.repairs_here() {
+            self.objects[index].durability = Some(max);
+            return tr!(
+                self,
+                "You repair the {} at the forge. It is as good as new.\n",
+                label
+            );
+        }
+        let kit = (0..self.objects.len()).find(|&pos| {
+            self.objects[pos].repair.is_some() && self.is_containing(Some(LOC_PLAYER), Some(pos))
+        });
+        match kit {
+            Some(kit) => {
+                let durability = (durability + self.objects[kit].repair.unwrap_or(0)).min(max);
+                self.objects[index].durability = Some(durability);
+                self.despawn(kit);
+                tr!(
+                    self,
+                    "You repair the {} with the {}. Durability: {}/{}\n",
+                    label,
+                    self.objects[kit].label[0],
+                    durability,
+                    max
+                )
+            }
+            None => self.refuse(tr!(self, "There is nothing here to repair it with.\n")),
+        }
+    }
+
+    /// Returns how much damage the player's armour absorbs
+    pub fn player_defence(&self) -> u64 {
+        (0..self.objects.len())
+            .filter(|&pos| self.is_containing(Some(LOC_PLAYER), Some(pos)))
+            .map(|pos| self.objects[pos].defence.unwrap_or(0))
+            .sum::<u64>()
+            + self.objects[LOC_PLAYER].defence.unwrap_or(0)
+    }
+
+    /// Works out an enemy's attack on the player from a hit roll and a dodge roll between 0 and 99
+    /// and a damage roll below the enemy's attack. Returns the combat log and the damage done.
+    pub fn resolve_enemy_attack(
+        &self,
+        enemy_index: usize,
+        hit_roll: u64,
+        dodge_roll: u64,
+        damage_roll: u64,
+    ) -> (String, u64) {
+        let enemy = &self.objects[enemy_index];
+        let accuracy = enemy.accuracy.unwrap_or(100);
+        let dodge = self.player_dodge();
+
+        if hit_roll >= accuracy {
+            return (
+                tr!(
+                    self,
+                    "\nThe {} misses. (rolled {}, needed under {})",
+                    enemy.label[0],
+                    hit_roll,
+                    accuracy
+                ),
+                0,
+            );
+        }
+        if dodge_roll < dodge {
+            return (
+                tr!(
+                    self,
+                    "\nYou dodged the attack (rolled {}, needed under {})",
+                    dodge_roll,
+                    dodge
+                ),
+                0,
+            );
+        }
+        let damage = damage_roll.saturating_mul(self.difficulty.settings().enemy_damage) / 100;
+        if damage == 0 {
+            return (tr!(self, "\nYou dodged the attack"), 0);
+        }
+        let defence = self.player_defence();
+        match damage.saturating_sub(defence) {
+            0 => (tr!(self, "\nThe blow glances off your armour"), 0),
+            damage if defence > 0 => (
+                tr!(
+                    self,
+                    "\nYou got hit for {} damage. Your armour absorbs {}",
+                    damage,
+                    defence
+                ),
+                damage,
+            ),
+            damage => (tr!(self, "\nYou got hit for {} damage", damage), damage),
+        }
+    }
+
+    /// Function to attack an enemy
+    pub fn do_attack(&mut self, noun: &String) -> String {
+        let (output, obj_opt) = self.object_visible(noun);
+
+        match obj_opt {
+            Some(obj_index) => {
+                if self.objects[obj_index].enemy {
+                    if self.health(obj_index).is_dead() {
+                        return self.refuse(tr!(
+                            self,
+                            "The {} is already dead.\n",
                             self.objects[obj_index].label[0]
-                        )
+                        ));
                     }
+                    self.mode = Mode::Fighting(obj_index);
+                    self.emit(Event::CombatStarted(obj_index));
+                    String::new()
                 } else {
-                    format!(
+                    self.refuse(tr!(
+                        self,
                         "You can't attack the {}.\n",
                         self.objects[obj_index].label[0]
-                    )
+                    ))
                 }
             }
-            None => output,
+            None => match self.enemy_over_there(noun) {
+                Some(enemy_index) => self.do_shoot(enemy_index),
+                None => self.refuse(output),
+            },
+        }
+    }
+
+    /// Creates a new object from a template at a location and returns its index. New objects
+    /// are always added at the end, so the indices of other objects never change.
+    pub fn spawn(&mut self, template: &str, location: Option<usize>) -> Option<usize> {
+        let mut object = self.templates.get(template)?.clone();
+        object.location = location;
+        if object.enemy {
+            object.health = object
+                .health
+                .map(|health| health.scaled(self.difficulty.settings().enemy_health));
+        }
+        object.template = Some(template.to_string());
+        self.objects.push(object);
+        Some(self.objects.len() - 1)
+    }
+
+    /// Removes an object from the game, along with everything it holds. Its index stays taken
+    /// so saved games and references to other objects stay valid.
+    pub fn despawn(&mut self, index: usize) {
+        for pos in 0..self.objects.len() {
+            if pos != index && self.objects[pos].location == Some(index) {
+                self.despawn(pos);
+            }
+        }
+        self.objects[index].location = None;
+        self.objects[index].despawned = true;
+        self.clear_effects(index);
+        if self.equipped == Some(index) {
+            self.equipped = None;
+        }
+    }
+
+    /// Returns an enemy's full health at the current difficulty
+    pub fn enemy_max_health(&self, enemy_index: usize) -> u64 {
+        Health(self.objects[enemy_index].max_health.unwrap_or(MAX_HEALTH))
+            .scaled(self.difficulty.settings().enemy_health)
+            .value()
+    }
+
+    /// Heals an enemy the player ran from, following its flee behaviour
+    pub fn apply_flee(&mut self, enemy_index: usize) {
+        let health_left = self.health(enemy_index).value();
+        let max = self.enemy_max_health(enemy_index);
+        let amount = match self.objects[enemy_index].on_flee {
+            FleeBehaviour::FullHeal => max,
+            FleeBehaviour::PartialHeal(percent) => {
+                max.saturating_sub(health_left) * percent.min(100) / 100
+            }
+            FleeBehaviour::None => 0,
+        };
+        self.heal(enemy_index, amount, max);
+    }
+
+    /// Lets a turn pass for the enemies: wounded ones regenerate and dead ones come back
+    /// once their respawn time is up
+    pub fn enemies_recover(&mut self) -> String {
+        let mut result = String::new();
+        let player_loc = self.objects[LOC_PLAYER].location;
+
+        for pos in 0..self.objects.len() {
+            let object = &self.objects[pos];
+            if !object.enemy || object.despawned {
+                continue;
+            }
+            let max = self.enemy_max_health(pos);
+            match (object.health, object.regen, object.respawn, object.died_at) {
+                (Some(health), _, Some(respawn), Some(died_at))
+                    if health.is_dead() && self.turn >= died_at.saturating_add(respawn) =>
+                {
+                    self.objects[pos].health = Some(Health(max));
+                    self.objects[pos].died_at = None;
+                    if self.objects[pos].location == player_loc {
+                        result += &tr!(self, "The {} is back!\n", self.objects[pos].label[0]);
+                    }
+                }
+                (Some(health), Some(regen), _, _) if !health.is_dead() => {
+                    self.heal(pos, regen, max);
+                }
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Counts a killed enemy and drops what it carried. Only the first death counts, so
+    /// respawning enemies can't be farmed for score and loot.
+    fn defeat_enemy(&mut self, enemy_index: usize) -> String {
+        self.objects[enemy_index].died_at = Some(self.turn);
+        self.emit(Event::EnemyKilled(enemy_index));
+        if std::mem::replace(&mut self.objects[enemy_index].looted, true) {
+            return self.advance_quests(None);
+        }
+        self.stats.enemies_defeated += 1;
+        self.drop_loot(enemy_index) + &self.advance_quests(None)
+    }
+
+    /// Returns an enemy in a location next to the player's
+    fn enemy_over_there(&self, noun: &str) -> Option<usize> {
+        match self.object_index(noun, Some(LOC_PLAYER), Distance::NotHere) {
+            AmbiguousOption::Some(index)
+                if self.objects[index].enemy
+                    && self.get_distance(Some(LOC_PLAYER), self.objects[index].location)
+                        == Distance::OverThere =>
+            {
+                Some(index)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the ranged weapon the player would shoot with: the equipped one, or else
+    /// the first one they carry
+    pub fn ranged_weapon(&self) -> Option<usize> {
+        self.equipped_weapon()
+            .filter(|&weapon| self.objects[weapon].ranged)
+            .or_else(|| {
+                (0..self.objects.len()).find(|&pos| {
+                    self.objects[pos].ranged
+                        && self.objects[pos].attack.is_some()
+                        && self.get_distance(Some(LOC_PLAYER), Some(pos)) == Distance::Held
+                })
+            })
+    }
+
+    /// Uses up one shot of the weapon's ammunition. Weapons without ammunition always fire.
+    pub fn take_ammo(&mut self, weapon_index: usize) -> Result<(), String> {
+        let ammo_index = match self.objects[weapon_index].ammo {
+            Some(ammo_index) => ammo_index,
+            None => return Ok(()),
+        };
+        let left = self.objects[ammo_index].quantity.unwrap_or(0);
+        if left == 0 || self.get_distance(Some(LOC_PLAYER), Some(ammo_index)) != Distance::Held {
+            return Err(tr!(
+                self,
+                "You have no {} for the {}!!\n",
+                self.objects[ammo_index].label[0].to_lowercase(),
+                self.objects[weapon_index].label[0]
+            ));
+        }
+        self.objects[ammo_index].quantity = Some(left - 1);
+        if left == 1 {
+            self.despawn(ammo_index);
+        }
+        Ok(())
+    }
+
+    /// Player shoots at an enemy in a neighbouring location. It is too far away to hit back.
+    pub fn do_shoot(&mut self, enemy_index: usize) -> String {
+        if self.health(enemy_index).is_dead() {
+            return self.refuse(tr!(
+                self,
+                "The {} is already dead.\n",
+                self.objects[enemy_index].label[0]
+            ));
+        }
+        let weapon_index = match self.ranged_weapon() {
+            Some(weapon_index) => weapon_index,
+            None => {
+                return self.refuse(tr!(
+                    self,
+                    "The {} is too far away. You need a ranged weapon to attack it from here.\n",
+                    self.objects[enemy_index].label[0]
+                ))
+            }
+        };
+        if let Err(output) = self.take_ammo(weapon_index) {
+            return self.refuse(output);
+        }
+        self.equipped = Some(weapon_index);
+
+        let mut rng = rand::thread_rng();
+        let (log, damage) = self.resolve_player_attack(
+            weapon_index,
+            enemy_index,
+            rng.gen_range(0..100),
+            rng.gen_range(0..100),
+        );
+        self.say(tr!(
+            self,
+            "You shoot from afar with the {}.\n{}",
+            self.objects[weapon_index].label[0],
+            log
+        ));
+        self.hit(enemy_index, damage);
+        self.say("\n");
+        let result = self.wear(weapon_index).trim_start().to_string();
+        if self.health(enemy_index).is_dead() {
+            result + &self.defeat_enemy(enemy_index)
+        } else {
+            result
+                + &tr!(
+                    self,
+                    "The {} is too far away to strike back.\n",
+                    self.objects[enemy_index].label[0]
+                )
+        }
+    }
+
+    /// Function to talk to an NPC
+    pub fn do_talk(&mut self, noun: &String) -> String {
+        let (output, obj_opt) = self.object_visible(noun);
+
+        match (obj_opt, self.get_distance(Some(LOC_PLAYER), obj_opt)) {
+            (Some(npc_index), Distance::Here) if self.objects[npc_index].dialogue.is_some() => {
+                self.mode = Mode::Talking {
+                    npc: npc_index,
+                    node: 0,
+                };
+                self.show_dialogue(npc_index, 0)
+            }
+            (Some(npc_index), Distance::Here) if self.objects[npc_index].npc => self.refuse(tr!(
+                self,
+                "The {} has nothing to say to you.\n",
+                self.objects[npc_index].label[0]
+            )),
+            (Some(_), Distance::OverThere) => {
+                self.refuse(tr!(self, "They are too far away to hear you.\n"))
+            }
+            (Some(obj_index), _) => self.refuse(tr!(
+                self,
+                "The {} doesn't answer.\n",
+                self.objects[obj_index].label[0]
+            )),
+            (None, _) => self.refuse(output),
+        }
+    }
+
+    /// Shows what an NPC says and the replies the player has. Ends the conversation when
+    /// there is nothing left to reply.
+    fn show_dialogue(&mut self, npc_index: usize, node: usize) -> String {
+        let options = self.dialogue_options(npc_index, node);
+        let Some(dialogue) = self.objects[npc_index]
+            .dialogue
+            .as_ref()
+            .and_then(|nodes| nodes.get(node))
+        else {
+            let text = tr!(
+                self,
+                "The {} has nothing more to say.\n",
+                self.objects[npc_index].label[0]
+            );
+            self.say(text);
+            return self.end_conversation(npc_index);
+        };
+        let event = Event::Dialogue {
+            npc: npc_index,
+            text: dialogue.text.clone(),
+            options: options
+                .iter()
+                .map(|&option| dialogue.options[option].text.clone())
+                .collect(),
+        };
+        self.emit(event);
+        if options.is_empty() {
+            self.end_conversation(npc_index)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Player picks one of the numbered replies in a conversation
+    pub fn do_choose(&mut self, npc_index: usize, node: usize, choice: usize) -> String {
+        let options = self.dialogue_options(npc_index, node);
+        if choice == 0 || choice > options.len() {
+            return self.refuse(tr!(self, TALK_HINT));
+        }
+        let (result, next) = self.choose_dialogue(npc_index, node, options[choice - 1]);
+        self.say(result);
+        match next {
+            Some(next_node) => {
+                self.mode = Mode::Talking {
+                    npc: npc_index,
+                    node: next_node,
+                };
+                self.show_dialogue(npc_index, next_node)
+            }
+            None => self.end_conversation(npc_index),
+        }
+    }
+
+    /// Player stops talking to an NPC
+    fn end_conversation(&mut self, npc_index: usize) -> String {
+        self.mode = Mode::Exploring;
+        self.emit(Event::ConversationEnded(npc_index));
+        self.advance_quests(Some(npc_index))
+    }
+
+    /// Returns the indices of the options of a dialogue node the player can currently choose
+    pub fn dialogue_options(&self, npc_index: usize, node: usize) -> Vec<usize> {
+        let nodes = match &self.objects[npc_index].dialogue {
+            Some(nodes) if node < nodes.len() => nodes,
+            _ => return Vec::new(),
+        };
+        nodes[node]
+            .options
+            .iter()
+            .enumerate()
+            .filter(|(_, option)| {
+                option
+                    .requires_flag
+                    .as_ref()
+                    .is_none_or(|flag| self.flags.contains(flag))
+                    && option
+                        .unless_flag
+                        .as_ref()
+                        .is_none_or(|flag| !self.flags.contains(flag))
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Applies the effects of a dialogue option and returns the node to continue from
+    pub fn choose_dialogue(
+        &mut self,
+        npc_index: usize,
+        node: usize,
+        option: usize,
+    ) -> (String, Option<usize>) {
+        let chosen = match self.objects[npc_index]
+            .dialogue
+            .as_ref()
+            .and_then(|nodes| nodes.get(node))
+            .and_then(|dialogue| dialogue.options.get(option))
+        {
+            Some(chosen) => chosen.clone(),
+            None => return (String::new(), None),
+        };
+        let mut result = String::new();
+
+        for effect in chosen.effects {
+            match effect {
+                DialogueEffect::SetFlag(flag) => {
+                    if !self.flags.contains(&flag) {
+                        self.flags.push(flag);
+                    }
+                }
+                DialogueEffect::GiveItem(item_index) => {
+                    if self.is_containing(Some(npc_index), Some(item_index)) {
+                        self.objects[item_index].location = Some(LOC_PLAYER);
+                        self.note_collected(Some(item_index));
+                        result += &tr!(
+                            self,
+                            "\nThe {} gives you the {}.\n",
+                            self.objects[npc_index].label[0],
+                            self.objects[item_index].label[0]
+                        );
+                    }
+                }
+                DialogueEffect::StartQuest(quest) => result += &self.start_quest(&quest),
+            }
+        }
+        (result, chosen.next)
+    }
+
+    /// Starts the quest with the given id if it hasn't been started yet
+    pub fn start_quest(&mut self, id: &str) -> String {
+        match self.quests.iter_mut().find(|quest| quest.id == id) {
+            Some(quest) if quest.status == QuestStatus::Inactive => {
+                quest.status = QuestStatus::Active;
+                tr!(self, "\nNew quest: {}\n{}\n", quest.name, quest.description)
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Checks if a quest objective has been met. `talked_to` is the NPC the player just spoke with.
+    fn objective_done(&self, objective: &Objective, talked_to: Option<usize>) -> bool {
+        match objective {
+            Objective::Reach(location) => self.objects[LOC_PLAYER].location == Some(*location),
+            Objective::Obtain(item) => self.is_containing(Some(LOC_PLAYER), Some(*item)),
+            Objective::Defeat(enemy) => self.objects[*enemy].health.is_some_and(Health::is_dead),
+            Objective::TalkTo(npc) => talked_to == Some(*npc),
+        }
+    }
+
+    /// Describes a quest objective for the journal
+    fn describe_objective(&self, objective: &Objective) -> String {
+        match objective {
+            Objective::Reach(location) => {
+                tr!(self, "Go to the {}", self.objects[*location].label[0])
+            }
+            Objective::Obtain(item) => tr!(self, "Find the {}", self.objects[*item].label[0]),
+            Objective::Defeat(enemy) => tr!(self, "Defeat the {}", self.objects[*enemy].label[0]),
+            Objective::TalkTo(npc) => tr!(self, "Talk to the {}", self.objects[*npc].label[0]),
+        }
+    }
+
+    /// Moves active quests forward and hands out the rewards of the ones that are completed
+    pub fn advance_quests(&mut self, talked_to: Option<usize>) -> String {
+        let mut result = String::new();
+
+        for quest_index in 0..self.quests.len() {
+            if self.quests[quest_index].status != QuestStatus::Active {
+                continue;
+            }
+            while self.quests[quest_index].progress < self.quests[quest_index].objectives.len()
+                && self.objective_done(
+                    &self.quests[quest_index].objectives[self.quests[quest_index].progress],
+                    talked_to,
+                )
+            {
+                self.quests[quest_index].progress += 1;
+            }
+            if self.quests[quest_index].progress == self.quests[quest_index].objectives.len() {
+                self.quests[quest_index].status = QuestStatus::Completed;
+                result += &tr!(
+                    self,
+                    "\nQuest completed: {}\n",
+                    self.quests[quest_index].name
+                );
+                result += &self.give_reward(self.quests[quest_index].reward.clone());
+            }
+        }
+        result
+    }
+
+    /// Gives the player the reward of a completed quest
+    fn give_reward(&mut self, reward: QuestReward) -> String {
+        let mut result = String::new();
+
+        for item in reward.items {
+            self.objects[item].location = Some(LOC_PLAYER);
+            self.note_collected(Some(item));
+            result += &tr!(self, "You received the {}.\n", self.objects[item].label[0]);
+        }
+        if let Some(heal) = reward.health {
+            self.heal(LOC_PLAYER, heal, MAX_HEALTH);
+            result += &tr!(
+                self,
+                "Your health has increased to {}\n",
+                self.health(LOC_PLAYER)
+            );
+        }
+        for flag in reward.flags {
+            if !self.flags.contains(&flag) {
+                self.flags.push(flag);
+            }
+        }
+        result
+    }
+
+    /// Lists the active and completed quests
+    pub fn do_quests(&self) -> String {
+        let mut active = String::new();
+        let mut completed = String::new();
+
+        for quest in &self.quests {
+            match quest.status {
+                QuestStatus::Active => {
+                    active += &format!("{}: {}\n", quest.name, quest.description);
+                    if let Some(objective) = quest.objectives.get(quest.progress) {
+                        active += &format!("  - {}\n", self.describe_objective(objective));
+                    }
+                }
+                QuestStatus::Completed => completed += &format!("{}\n", quest.name),
+                QuestStatus::Inactive => {}
+            }
+        }
+
+        if active.is_empty() && completed.is_empty() {
+            tr!(self, "You have no quests yet.\n")
+        } else {
+            let mut result = String::new();
+            if !active.is_empty() {
+                result += &tr!(self, "Active quests:\n{}", active);
+            }
+            if !completed.is_empty() {
+                result += &tr!(self, "\nCompleted quests:\n{}", completed);
+            }
+            result
+        }
+    }
+
+    /// Look around the surroundings of the location the player is in
+    pub fn do_look(&self, noun: &str) -> String {
+        match noun {
+            "" => {
+                let (list, _) = self.list_objects(self.objects[LOC_PLAYER].location.unwrap());
+                tr!(
+                    self,
+                    " You are in the {}\n {}.\n",
+                    self.objects[self.objects[LOC_PLAYER].location.unwrap()].label[0],
+                    self.objects[self.objects[LOC_PLAYER].location.unwrap()].description
+                ) + list.as_str()
+            }
+            _ => self.do_examine(&noun.to_string()),
+        }
+    }
+
+    /// Describes a single object in detail
+    pub fn do_examine(&self, noun: &String) -> String {
+        let (output, obj_opt) = self.object_visible(noun);
+        let obj_index = match obj_opt {
+            Some(obj_index) => obj_index,
+            None => return output.trim_end().to_string() + "\n",
+        };
+        let object = &self.objects[obj_index];
+        let mut result = format!("{}\n{}\n", object.label[0], object.description);
+
+        if let Some(details) = &object.details {
+            result += &format!("{}\n", details);
+        }
+        match (object.enemy || obj_index == LOC_PLAYER, object.health) {
+            (true, Some(health)) if health.is_dead() => result += &tr!(self, "It is dead.\n"),
+            (true, Some(health)) => result += &tr!(self, "Health: {}\n", health),
+            (false, Some(heal)) if object.consumable == Some(true) => {
+                result += &tr!(self, "Heals: {}\n", heal)
+            }
+            _ => {}
+        }
+        if let Some(attack) = object.attack {
+            result += &tr!(self, "Attack: {}\n", attack);
+        }
+        if let Some(accuracy) = object.accuracy {
+            result += &tr!(self, "Accuracy: {}%\n", accuracy);
+        }
+        if let Some(crit_chance) = object.crit_chance {
+            result += &tr!(
+                self,
+                "Critical hits: {}% chance for {}% damage\n",
+                crit_chance,
+                object.crit_multiplier.unwrap_or(200)
+            );
+        }
+        if let Some(defence) = object.defence {
+            result += &tr!(self, "Defence: {}\n", defence);
+        }
+        if let Some(dodge) = object.dodge {
+            result += &tr!(self, "Dodge: {}%\n", dodge);
+        }
+        if object.ranged {
+            result += &tr!(self, "It can hit enemies in a neighbouring location.\n");
+        }
+        if let (Some(durability), Some(max)) = (object.durability, object.max_durability) {
+            result += &tr!(self, "Durability: {}/{}\n", durability, max);
+        }
+        if let Some(repair) = object.repair {
+            result += &tr!(self, "Repairs: {}\n", repair);
+        }
+        if object.repairs {
+            result += &tr!(self, "Gear can be repaired here.\n");
+        }
+        if let Some(ammo) = object.ammo {
+            result += &tr!(self, "Uses: {}\n", self.objects[ammo].label[0]);
+        }
+        if let Some(quantity) = object.quantity {
+            result += &tr!(self, "Left: {}\n", quantity);
+        }
+        if let Some(regen) = object.regen {
+            result += &tr!(self, "Regenerates {} health a turn.\n", regen);
+        }
+        if let Some(effect) = &object.effect {
+            result += &tr!(
+                self,
+                "Effect: {} for {} turns",
+                self.catalog.word(effect.kind),
+                effect.turns
+            );
+            if let Some(chance) = effect.chance {
+                result += &tr!(self, " ({}% chance)", chance);
+            }
+            result += "\n";
+        }
+        let effects = self.describe_effects(obj_index);
+        if !effects.is_empty() {
+            result += &tr!(self, "Affected by: {}\n", effects);
+        }
+        if let Some(gold) = object.gold.filter(|_| object.item == Some(true)) {
+            result += &tr!(self, "Worth {} gold.\n", gold);
+        }
+        if let Some(price) = object.price {
+            result += &tr!(self, "Value: {} gold\n", price);
+        }
+        match (object.location, object.destination) {
+            (Some(_), Some(destination)) => {
+                result += &tr!(
+                    self,
+                    "It leads to the {}.\n",
+                    self.objects[destination].label[0]
+                )
+            }
+            (None, None) if self.get_distance(Some(LOC_PLAYER), obj_opt) == Distance::OverThere => {
+                result += &tr!(self, "It is not far from here.\n")
+            }
+            _ => {}
+        }
+        result
+    }
+
+    /// Player goes to the specified location
+    pub fn do_go(&mut self, noun: &String) -> String {
+        let (output, obj_opt) = self.object_visible(noun);
+
+        match self.get_distance(Some(LOC_PLAYER), obj_opt) {
+            Distance::OverThere => self.enter_location(obj_opt.unwrap()),
+            Distance::NotHere => self.refuse(tr!(self, "You don't see any '{}' here.\n", noun)),
+            Distance::Unknown => self.refuse(output),
+            _ => {
+                let obj_dist = obj_opt.and_then(|a| self.objects[a].destination);
+                if let Some(destination) = obj_dist {
+                    self.enter_location(destination)
+                } else {
+                    let obj_desc = obj_opt.map(|a| self.objects[a].description.clone());
+                    self.refuse(obj_desc.unwrap_or(tr!(self, "Invalid command!!\n")))
+                }
+            }
+        }
+    }
+
+    /// Moves the player into a location and describes what they find there
+    pub fn enter_location(&mut self, location: usize) -> String {
+        let first_visit = self.move_player(location);
+        self.emit(Event::Moved {
+            location,
+            first_visit,
+        });
+        self.describe_arrival(location, first_visit) + &self.advance_quests(None)
+    }
+
+    /// Puts the player in a location. Returns whether they have never been there before.
+    fn move_player(&mut self, location: usize) -> bool {
+        if let Some(current) = self.objects[LOC_PLAYER].location {
+            self.mark_visited(current);
+        }
+        let first_visit = !self.objects[location].visited;
+        self.objects[LOC_PLAYER].location = Some(location);
+        self.mark_visited(location);
+        first_visit
+    }
+
+    /// Describes a location the player has just arrived in
+    fn describe_arrival(&self, location: usize, first_visit: bool) -> String {
+        if first_visit {
+            self.do_look("")
+                + &self.objects[location]
+                    .details
+                    .as_ref()
+                    .map(|details| format!("\n{}\n", details))
+                    .unwrap_or_default()
+        } else {
+            let (list, _) = self.list_objects(location);
+            tr!(
+                self,
+                " You return to the {}.\n",
+                self.objects[location].label[0]
+            ) + &list
+        }
+    }
+
+    /// Marks a location as visited and the passages leading out of it as discovered
+    fn mark_visited(&mut self, location: usize) {
+        self.objects[location].visited = true;
+        for passage in 0..self.objects.len() {
+            if self.objects[passage].location == Some(location)
+                && self.objects[passage].destination.is_some()
+            {
+                self.objects[passage].discovered = true;
+            }
         }
     }
 
-    /// Look around the surroundings of the location the player is in
-    pub fn do_look(&self, noun: &str) -> String {
-        match noun {
-            "" => {
-                let (list, _) = self.list_objects(self.objects[LOC_PLAYER].location.unwrap());
-                format!(
-                    " You are in the {}\n {}.\n",
-                    self.objects[self.objects[LOC_PLAYER].location.unwrap()].label[0],
-                    self.objects[self.objects[LOC_PLAYER].location.unwrap()].description
-                ) + list.as_str()
+    /// Checks if the player knows about a passage. The ones at the current location always count.
+    pub fn is_discovered(&self, passage: usize) -> bool {
+        self.objects[passage].discovered
+            || (self.objects[passage].location.is_some()
+                && self.objects[passage].location == self.objects[LOC_PLAYER].location)
+    }
+
+    /// Finds the shortest way to a location using the passages the player knows about.
+    /// Only goes through locations the player has visited. Returns the passages to take.
+    pub fn find_route(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut came_from: HashMap<usize, (usize, usize)> = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(location) = queue.pop_front() {
+            if location == to {
+                let mut route = Vec::new();
+                let mut step = to;
+                while let Some(&(previous, passage)) = came_from.get(&step) {
+                    route.push(passage);
+                    step = previous;
+                }
+                route.reverse();
+                return Some(route);
+            }
+            for (pos, passage) in self.objects.iter().enumerate() {
+                match (passage.location, passage.destination) {
+                    (Some(loc), Some(destination))
+                        if loc == location
+                            && destination != from
+                            && self.is_discovered(pos)
+                            && self.is_visited(destination)
+                            && !came_from.contains_key(&destination) =>
+                    {
+                        came_from.insert(destination, (location, pos));
+                        queue.push_back(destination);
+                    }
+                    _ => continue,
+                }
             }
-            _ => "Invalid command!!\n".to_string(),
         }
+        None
     }
 
-    /// Player goes to the specified location
-    pub fn do_go(&mut self, noun: &String) -> String {
-        let (output, obj_opt) = self.object_visible(noun);
+    /// Checks if there is a living enemy at a location
+    fn enemy_at(&self, location: usize) -> Option<usize> {
+        (0..self.objects.len()).find(|&pos| {
+            self.objects[pos].enemy
+                && self.objects[pos].location == Some(location)
+                && !self.health(pos).is_dead()
+        })
+    }
 
-        match self.get_distance(Some(LOC_PLAYER), obj_opt) {
-            Distance::OverThere => {
-                self.objects[LOC_PLAYER].location = obj_opt;
-                "OK.\n".to_string() + &self.do_look("")
+    /// Player travels to a location they have been to before, one step at a time
+    pub fn do_travel(&mut self, noun: &String) -> String {
+        let player_loc = match self.objects[LOC_PLAYER].location {
+            Some(player_loc) => player_loc,
+            None => return self.refuse(tr!(self, "You have no idea where you are.\n")),
+        };
+        let target = self.best_match(
+            noun,
+            (0..self.objects.len())
+                .filter(|&pos| self.objects.iter().any(|o| o.destination == Some(pos))),
+        );
+        let target = match target {
+            AmbiguousOption::Some(target) if target == player_loc => {
+                return self.refuse(tr!(
+                    self,
+                    "You are already in the {}.\n",
+                    self.objects[target].label[0]
+                ))
             }
-            Distance::NotHere => {
-                format!("You don't see any '{}' here.\n", noun)
+            AmbiguousOption::Some(target) if self.is_visited(target) => target,
+            AmbiguousOption::Some(target) => {
+                return self.refuse(tr!(
+                    self,
+                    "You don't know the way to the {} yet.\n",
+                    self.objects[target].label[0]
+                ))
             }
-            Distance::Unknown => output,
-            _ => {
-                let obj_dist = obj_opt.and_then(|a| self.objects[a].destination);
-                if obj_dist.is_some() {
-                    self.objects[LOC_PLAYER].location = obj_dist;
-                    "OK.\n".to_string() + &self.do_look("")
-                } else {
-                    let obj_desc = obj_opt.map(|a| self.objects[a].description.clone());
-                    obj_desc.unwrap_or("Invalid command!!\n".to_string())
+            AmbiguousOption::Ambiguous => {
+                return self.refuse(tr!(
+                    self,
+                    "Please be more specific about which place '{}' you mean.\n",
+                    noun
+                ))
+            }
+            AmbiguousOption::None => {
+                return self.refuse(tr!(
+                    self,
+                    "You don't know of any place called '{}'.\n",
+                    noun
+                ))
+            }
+        };
+        let route = match self.find_route(player_loc, target) {
+            Some(route) => route,
+            None => return self.refuse(tr!(self, "You don't know the way to the {}.\n", noun)),
+        };
+
+        for (step, passage) in route.iter().enumerate() {
+            // Every step is a turn of its own, so effects and enemies keep pace with the walk
+            if step > 0 {
+                let recovered = self.pass_turn();
+                self.say(recovered);
+                if self.health(LOC_PLAYER).is_dead() {
+                    return String::new();
                 }
+                self.turn += 1;
+            }
+            let destination = self.objects[*passage].destination.unwrap();
+            let quests_before: Vec<(QuestStatus, usize)> =
+                self.quests.iter().map(|q| (q.status, q.progress)).collect();
+            let first_visit = self.move_player(destination);
+            let quests = self.advance_quests(None);
+            let quests_changed = self
+                .quests
+                .iter()
+                .map(|q| (q.status, q.progress))
+                .ne(quests_before);
+            let blocker = self.enemy_at(destination);
+
+            if step < route.len() - 1 && blocker.is_none() && !quests_changed {
+                self.emit(Event::PassedThrough {
+                    passage: *passage,
+                    location: destination,
+                });
+                continue;
+            }
+            self.emit(Event::Moved {
+                location: destination,
+                first_visit,
+            });
+            let arrival = self.describe_arrival(destination, first_visit);
+            self.say(arrival + &quests);
+            if step == route.len() - 1 {
+                break;
+            } else if let Some(enemy) = blocker {
+                return self.refuse(tr!(
+                    self,
+                    "\nYou stop. The {} blocks your way.\n",
+                    self.objects[enemy].label[0]
+                ));
+            } else {
+                return self.refuse(tr!(self, "\nYou stop to take stock.\n"));
             }
         }
+        String::new()
     }
 
     /// Player drops the specified object
@@ -784,30 +3437,43 @@ impl World {
             self.get_possession(Some(LOC_PLAYER), Command::Drop("drop".to_string()), noun);
 
         let player_loc = self.objects[LOC_PLAYER].location;
-        output + self.move_object(object_index, player_loc).as_str()
+        let moved = self.move_object(object_index, player_loc);
+        match object_index {
+            Some(item) if player_loc.is_some() && self.objects[item].location == player_loc => {
+                self.say(output);
+                self.emit(Event::ItemDropped(item));
+                String::new()
+            }
+            _ => self.refuse(output + moved.as_str()),
+        }
     }
 
     /// Player consumes the specified object
     pub fn do_consume(&mut self, object: Option<usize>) -> String {
-        let heal = self.objects[object.unwrap()].health.unwrap_or(0);
-        let mut player_health = self.objects[LOC_PLAYER].health.unwrap_or(0);
-        if player_health == 100 {
-            "You are already at full health".to_string()
-        } else {
-            self.objects[LOC_PLAYER].health = Some(
-                self.objects[LOC_PLAYER]
-                    .health
-                    .map(|h| h + heal)
-                    .unwrap_or(0),
-            );
-            player_health = self.objects[LOC_PLAYER].health.unwrap_or(0);
-            if player_health > 100 {
-                self.objects[LOC_PLAYER].health = Some(100);
-            }
-            self.objects[object.unwrap()].location = None;
-            "You have consumed the item. Your health has increased to ".to_string()
-                + &self.objects[LOC_PLAYER].health.unwrap_or(0).to_string()
+        let index = match object {
+            Some(index) => index,
+            None => return self.refuse(tr!(self, "You cannot consume that!!\n")),
+        };
+        let heal = self
+            .health(index)
+            .scaled(self.difficulty.settings().healing)
+            .value();
+        if let Some(effect) = self.objects[index].effect.clone() {
+            self.heal(LOC_PLAYER, heal, MAX_HEALTH);
+            self.despawn(index);
+            tr!(self, "You have consumed the item.")
+                + &self.apply_effect(LOC_PLAYER, &effect)
                 + "\n"
+        } else if self.health(LOC_PLAYER).value() >= MAX_HEALTH {
+            self.refuse(tr!(self, "You are already at full health"))
+        } else {
+            self.heal(LOC_PLAYER, heal, MAX_HEALTH);
+            self.despawn(index);
+            self.emit(Event::ItemConsumed {
+                item: index,
+                health: self.health(LOC_PLAYER).value(),
+            });
+            String::new()
         }
     }
 
@@ -821,31 +3487,514 @@ impl World {
             .unwrap_or(false);
 
         match (player_to_obj, obj_opt, obj_item, obj_consumable) {
-            (Distance::Player, _, _, _) => output + "Invalid!! You cannot get that!!",
-            (Distance::Held, Some(obj_index), true, _) => {
+            (Distance::Player, _, _, _) => {
+                self.refuse(output + &tr!(self, "Invalid!! You cannot get that!!"))
+            }
+            (Distance::Held, Some(obj_index), true, _) => self.refuse(
                 output
-                    + &format!(
+                    + &tr!(
+                        self,
                         "You already have: {}.\n",
                         self.objects[obj_index].description
-                    )
+                    ),
+            ),
+            (Distance::OverThere, _, true, _) => {
+                self.refuse(output + &tr!(self, "The item is not here. Try elsewhere!!\n"))
             }
-            (Distance::OverThere, _, true, _) => output + "The item is not here. Try elsewhere!!\n",
-            (Distance::OverThere, _, false, false) => output + "You cannot get that!!\n",
-            (Distance::Here, _, false, false) => output + "You cannot get that!!\n",
-            (Distance::Unknown, _, false, false) => output,
+            (Distance::OverThere, _, false, false) | (Distance::Here, _, false, false) => {
+                self.refuse(output + &tr!(self, "You cannot get that!!\n"))
+            }
+            (Distance::Unknown, _, false, false) => self.refuse(output),
             (Distance::Here, _, true, true) => self.do_consume(obj_opt),
-            _ => self.move_object(obj_opt, Some(LOC_PLAYER)),
+            (Distance::Here, Some(obj_index), true, _)
+                if self.objects[obj_index].gold.is_some() =>
+            {
+                self.collect_gold(obj_index)
+            }
+            _ => {
+                let from = obj_opt.and_then(|a| self.objects[a].location);
+                let output = self.move_object(obj_opt, Some(LOC_PLAYER));
+                match (obj_opt, from) {
+                    (Some(item), Some(from)) if self.is_containing(Some(LOC_PLAYER), obj_opt) => {
+                        self.emit(Event::ItemPickedUp { item, from })
+                    }
+                    _ => return self.refuse(output),
+                }
+                self.note_collected(obj_opt);
+                self.advance_quests(None)
+            }
         }
     }
 
     /// Player checks the inventory
     pub fn do_inventory(&self) -> String {
         let (list_string, count) = self.list_objects(LOC_PLAYER);
+        let purse = match self.objects[LOC_PLAYER].gold {
+            Some(gold) if gold > 0 => tr!(self, "\nYou have {} gold.\n", gold),
+            _ => String::new(),
+        };
         if count == 0 {
-            "You currently do not have anything in your inventory.\n".to_string()
+            tr!(
+                self,
+                "You currently do not have anything in your inventory.\n"
+            ) + &purse
+        } else {
+            list_string + &purse
+        }
+    }
+
+    /// Player combines two items they are holding, like "combine bones with spear"
+    pub fn do_combine(&mut self, noun: &str) -> String {
+        let (first, second) = match noun.split_once(" with ").or(noun.split_once(" and ")) {
+            Some(names) => names,
+            None => {
+                return self.refuse(tr!(
+                    self,
+                    "Combine what with what? Try: combine <item> with <item>\n"
+                ))
+            }
+        };
+        let mut items = Vec::new();
+        for name in [first, second] {
+            let (output, object_index) = self.get_possession(
+                Some(LOC_PLAYER),
+                Command::Combine("combine".to_string()),
+                &name.trim().to_string(),
+            );
+            match object_index {
+                Some(index) => items.push(index),
+                None => return self.refuse(output),
+            }
+        }
+        items.sort();
+
+        let recipe = self.recipes.iter().position(|recipe| {
+            let mut ingredients = recipe.ingredients.clone();
+            ingredients.sort();
+            ingredients == items
+        });
+        match recipe {
+            Some(recipe) => self.craft_recipe(recipe),
+            None => self.refuse(tr!(
+                self,
+                "The {} and the {} don't go together.\n",
+                self.objects[items[0]].label[0],
+                self.objects[items[1]].label[0]
+            )),
+        }
+    }
+
+    /// Player makes an item from a recipe they know
+    pub fn do_craft(&mut self, noun: &String) -> String {
+        let recipe = self.recipes.iter().position(|recipe| {
+            recipe.known
+                && self
+                    .templates
+                    .get(&recipe.result)
+                    .is_some_and(|template| self.object_with_label(template, noun))
+        });
+        let recipe = match recipe {
+            Some(recipe) => recipe,
+            None => return self.refuse(tr!(self, "You don't know how to make a {}.\n", noun)),
+        };
+        let missing: Vec<String> = self.recipes[recipe]
+            .ingredients
+            .iter()
+            .filter(|&&ingredient| !self.is_containing(Some(LOC_PLAYER), Some(ingredient)))
+            .map(|&ingredient| self.objects[ingredient].label[0].clone())
+            .collect();
+        if missing.is_empty() {
+            self.craft_recipe(recipe)
+        } else {
+            self.refuse(tr!(self, "You still need: {}\n", missing.join(", ")))
+        }
+    }
+
+    /// Uses up the ingredients of a recipe and gives the player the result
+    fn craft_recipe(&mut self, recipe: usize) -> String {
+        let ingredients = self.recipes[recipe].ingredients.clone();
+        let result = match self.spawn(&self.recipes[recipe].result.clone(), Some(LOC_PLAYER)) {
+            Some(result) => result,
+            None => return self.refuse(tr!(self, "Nothing comes of it.\n")),
+        };
+        for &ingredient in &ingredients {
+            self.despawn(ingredient);
+        }
+        self.note_collected(Some(result));
+
+        let names: Vec<String> = ingredients
+            .iter()
+            .map(|&ingredient| tr!(self, "the {}", self.objects[ingredient].label[0]))
+            .collect();
+        let mut output = tr!(
+            self,
+            "You combine {} into {}\n",
+            names.join(&tr!(self, " and ")),
+            self.objects[result].description.to_lowercase()
+        );
+        if !self.recipes[recipe].known {
+            self.recipes[recipe].known = true;
+            output += &tr!(self, "You learned a new recipe!\n");
+        }
+        output
+    }
+
+    /// Lists the recipes the player knows
+    pub fn do_recipes(&self) -> String {
+        let mut result = String::new();
+        for recipe in self.recipes.iter().filter(|recipe| recipe.known) {
+            let names: Vec<String> = recipe
+                .ingredients
+                .iter()
+                .map(|&ingredient| self.objects[ingredient].label[0].clone())
+                .collect();
+            let name = self
+                .templates
+                .get(&recipe.result)
+                .map(|template| template.label[0].clone())
+                .unwrap_or(recipe.result.clone());
+            result += &format!("{}: {}\n", name, names.join(" + "));
+        }
+        if result.is_empty() {
+            tr!(self, "You don't know any recipes yet.\n")
         } else {
-            list_string
+            tr!(self, "Known recipes:\n") + &result
+        }
+    }
+
+    /// Player readies a weapon they are holding
+    pub fn do_equip(&mut self, noun: &String) -> String {
+        let (output, object_index) =
+            self.get_possession(Some(LOC_PLAYER), Command::Equip("equip".to_string()), noun);
+
+        match object_index {
+            Some(index) if self.objects[index].attack.is_some() => {
+                self.equipped = object_index;
+                tr!(self, "You ready the {}.\n", self.objects[index].label[0])
+            }
+            Some(index) => self.refuse(tr!(
+                self,
+                "The {} is not a weapon.\n",
+                self.objects[index].label[0]
+            )),
+            None => self.refuse(output),
+        }
+    }
+
+    /// Returns the weapon the player has ready, if they are still holding it
+    pub fn equipped_weapon(&self) -> Option<usize> {
+        self.equipped
+            .filter(|&weapon| self.is_containing(Some(LOC_PLAYER), Some(weapon)))
+    }
+
+    /// Remembers that the player has got hold of an item, for the score
+    fn note_collected(&mut self, item: Option<usize>) {
+        if let Some(item) = item {
+            if self.is_containing(Some(LOC_PLAYER), Some(item))
+                && !self.stats.items_collected.contains(&item)
+            {
+                self.stats.items_collected.push(item);
+            }
+        }
+    }
+
+    /// Returns the lines that make up the score: a name, how many, and the points they're worth
+    pub fn score_breakdown(&self) -> Vec<(&'static str, u64, i64)> {
+        let quests_completed = self
+            .quests
+            .iter()
+            .filter(|quest| quest.status == QuestStatus::Completed)
+            .count() as u64;
+        let locations_visited = (0..self.objects.len())
+            .filter(|&pos| {
+                self.is_visited(pos) && self.objects.iter().any(|o| o.destination == Some(pos))
+            })
+            .count() as u64;
+        let enemies = self.stats.enemies_defeated;
+        let items = self.stats.items_collected.len() as u64;
+        let damage = self.stats.damage_taken;
+
+        vec![
+            ("Enemies defeated", enemies, enemies as i64 * 100),
+            (
+                "Quests completed",
+                quests_completed,
+                quests_completed as i64 * 50,
+            ),
+            ("Items collected", items, items as i64 * 10),
+            (
+                "Locations visited",
+                locations_visited,
+                locations_visited as i64 * 20,
+            ),
+            ("Damage taken", damage, -(damage as i64 / 2)),
+            ("Turns used", self.turn, -(self.turn as i64)),
+        ]
+    }
+
+    /// Returns the player's score so far
+    pub fn score(&self) -> u64 {
+        let total: i64 = self
+            .score_breakdown()
+            .iter()
+            .map(|(_, _, points)| points)
+            .sum();
+        total.max(0) as u64
+    }
+
+    /// A summary of the game with the score broken down
+    pub fn display_summary(&self) -> String {
+        let difficulty = match self.difficulty {
+            Difficulty::Custom(settings) => tr!(
+                self,
+                "Custom (enemy damage {}%, enemy health {}%, healing {}%, dodge {}%)",
+                settings.enemy_damage,
+                settings.enemy_health,
+                settings.healing,
+                settings.dodge
+            ),
+            difficulty => self.catalog.word(difficulty),
+        };
+        let mut result = tr!(self, "\n===== Summary =====\nDifficulty: {}\n", difficulty);
+        let effects = self.describe_effects(LOC_PLAYER);
+        if !effects.is_empty() {
+            result += &tr!(self, "Effects: {}\n", effects);
+        }
+        for (name, count, points) in self.score_breakdown() {
+            result += &format!("{:<18} {:>5} {:>+7}\n", tr!(self, name), count, points);
+        }
+        result + &format!("{:<18} {:>13}\n", tr!(self, "Score"), self.score())
+    }
+
+    /// One line summary of the player's state for the status bar
+    pub fn status_line(&self) -> String {
+        let location = self.objects[LOC_PLAYER]
+            .location
+            .map(|loc| self.objects[loc].label[0].clone())
+            .unwrap_or_default();
+        let weapon = self
+            .equipped_weapon()
+            .map(|weapon| self.objects[weapon].label[0].clone())
+            .unwrap_or(tr!(self, "none"));
+        tr!(
+            self,
+            "[{}] Health: {}/{} | Weapon: {} | Turn: {} | Score: {}",
+            location,
+            self.health(LOC_PLAYER),
+            MAX_HEALTH,
+            weapon,
+            self.turn,
+            self.score()
+        )
+    }
+
+    /// Player eats or drinks a consumable they are holding
+    pub fn do_eat(&mut self, noun: &String) -> String {
+        let (output, object_index) =
+            self.get_possession(Some(LOC_PLAYER), Command::Consume("eat".to_string()), noun);
+
+        match object_index {
+            Some(index) if self.objects[index].consumable == Some(true) => {
+                self.do_consume(object_index)
+            }
+            Some(index) => self.refuse(tr!(
+                self,
+                "You can't eat the {}.\n",
+                self.objects[index].label[0]
+            )),
+            None => self.refuse(output),
+        }
+    }
+
+    /// Adds a pile of gold to the player's purse
+    pub fn collect_gold(&mut self, obj_index: usize) -> String {
+        let amount = self.objects[obj_index].gold.unwrap_or(0);
+        self.objects[LOC_PLAYER].gold = Some(self.objects[LOC_PLAYER].gold.unwrap_or(0) + amount);
+        self.despawn(obj_index);
+        tr!(
+            self,
+            "You pick up {} gold. You now have {} gold.\n",
+            amount,
+            self.objects[LOC_PLAYER].gold.unwrap_or(0)
+        )
+    }
+
+    /// A defeated enemy drops everything it was carrying
+    pub fn drop_loot(&mut self, enemy_index: usize) -> String {
+        let mut result = String::new();
+        let enemy_loc = self.objects[enemy_index].location;
+
+        for template in self.objects[enemy_index].loot.clone() {
+            self.spawn(&template, Some(enemy_index));
+        }
+        for pos in 0..self.objects.len() {
+            if self.is_containing(Some(enemy_index), Some(pos)) {
+                self.objects[pos].location = enemy_loc;
+                result += &tr!(
+                    self,
+                    "The {} dropped {}\n",
+                    self.objects[enemy_index].label[0],
+                    self.objects[pos].description
+                );
+            }
+        }
+        result
+    }
+
+    /// Returns the merchant at the player's location
+    fn merchant_here(&self) -> Option<usize> {
+        (0..self.objects.len()).find(|&pos| {
+            self.objects[pos].merchant
+                && self.get_distance(Some(LOC_PLAYER), Some(pos)) == Distance::Here
+        })
+    }
+
+    /// Lists what the merchant at the player's location has for sale
+    pub fn do_list(&self) -> String {
+        let merchant = match self.merchant_here() {
+            Some(merchant) => merchant,
+            None => return tr!(self, "There is no one here to trade with.\n"),
+        };
+        let mut result = tr!(
+            self,
+            "The {} has for sale:\n",
+            self.objects[merchant].label[0]
+        );
+        let mut count = 0;
+
+        for (pos, object) in self.objects.iter().enumerate() {
+            if let (true, Some(price)) =
+                (self.is_containing(Some(merchant), Some(pos)), object.price)
+            {
+                count += 1;
+                result += &tr!(self, "{} - {} gold\n", object.label[0], price);
+            }
+        }
+        if count == 0 {
+            result = tr!(
+                self,
+                "The {} has nothing for sale.\n",
+                self.objects[merchant].label[0]
+            );
+        }
+        result
+            + &tr!(
+                self,
+                "You have {} gold.\n",
+                self.objects[LOC_PLAYER].gold.unwrap_or(0)
+            )
+    }
+
+    /// Player buys an item from the merchant at their location
+    pub fn do_buy(&mut self, noun: &String) -> String {
+        let merchant = match self.merchant_here() {
+            Some(merchant) => merchant,
+            None => return self.refuse(tr!(self, "There is no one here to trade with.\n")),
+        };
+        let item = match self.object_index(noun, Some(merchant), Distance::Held) {
+            AmbiguousOption::Some(item) => item,
+            AmbiguousOption::Ambiguous => {
+                return self.refuse(tr!(
+                    self,
+                    "Please be more specific about which {} you want to buy.\n",
+                    noun
+                ))
+            }
+            AmbiguousOption::None => {
+                return self.refuse(tr!(
+                    self,
+                    "The {} doesn't have any {}.\n",
+                    self.objects[merchant].label[0],
+                    noun
+                ))
+            }
+        };
+        let price = match self.objects[item].price {
+            Some(price) => price,
+            None => {
+                return self.refuse(tr!(
+                    self,
+                    "The {} is not for sale.\n",
+                    self.objects[item].label[0]
+                ));
+            }
+        };
+        let purse = self.objects[LOC_PLAYER].gold.unwrap_or(0);
+        if purse < price {
+            return self.refuse(tr!(
+                self,
+                "The {} costs {} gold, but you only have {} gold.\n",
+                self.objects[item].label[0],
+                price,
+                purse
+            ));
+        }
+
+        self.objects[LOC_PLAYER].gold = Some(purse - price);
+        if let Some(gold) = self.objects[merchant].gold {
+            self.objects[merchant].gold = Some(gold + price);
+        }
+        self.move_object(Some(item), Some(LOC_PLAYER));
+        self.emit(Event::ItemPickedUp {
+            item,
+            from: merchant,
+        });
+        self.note_collected(Some(item));
+        tr!(
+            self,
+            "You paid {} gold. You have {} gold left.\n",
+            price,
+            purse - price
+        ) + &self.advance_quests(None)
+    }
+
+    /// Player sells an item they are holding to the merchant at their location
+    pub fn do_sell(&mut self, noun: &String) -> String {
+        let merchant = match self.merchant_here() {
+            Some(merchant) => merchant,
+            None => return self.refuse(tr!(self, "There is no one here to trade with.\n")),
+        };
+        let (output, object_index) =
+            self.get_possession(Some(LOC_PLAYER), Command::Sell("sell".to_string()), noun);
+        let item = match object_index {
+            Some(item) => item,
+            None => return self.refuse(output),
+        };
+        let offer = match self.objects[item].price {
+            Some(price) => (price / 2).max(1),
+            None => {
+                return self.refuse(tr!(
+                    self,
+                    "The {} isn't interested in the {}.\n",
+                    self.objects[merchant].label[0],
+                    self.objects[item].label[0]
+                ))
+            }
+        };
+        match self.objects[merchant].gold {
+            Some(gold) if gold < offer => {
+                return self.refuse(tr!(
+                    self,
+                    "The {} can't afford to pay {} gold for the {}.\n",
+                    self.objects[merchant].label[0],
+                    offer,
+                    self.objects[item].label[0]
+                ))
+            }
+            Some(gold) => self.objects[merchant].gold = Some(gold - offer),
+            None => {}
         }
+
+        self.move_object(Some(item), Some(merchant));
+        self.objects[LOC_PLAYER].gold = Some(self.objects[LOC_PLAYER].gold.unwrap_or(0) + offer);
+        tr!(
+            self,
+            "You sold the {} to the {} for {} gold. You now have {} gold.\n",
+            self.objects[item].label[0],
+            self.objects[merchant].label[0],
+            offer,
+            self.objects[LOC_PLAYER].gold.unwrap_or(0)
+        )
     }
 
     /// Returns true or false depending on if the object is contained by another object
@@ -883,25 +4032,37 @@ impl World {
             (Some(obj_opt_idx), _, Some(to_idx), Some(player_loc_idx))
                 if to_idx == player_loc_idx =>
             {
-                format!("You have dropped {}.\n", self.objects[obj_opt_idx].label[0])
+                tr!(
+                    self,
+                    "You have dropped {}.\n",
+                    self.objects[obj_opt_idx].label[0]
+                )
             }
             (Some(obj_opt_idx), _, Some(to_idx), _) if to_idx != LOC_PLAYER => {
-                format!(
+                tr!(
+                    self,
                     "You put {} in {}.\n",
-                    self.objects[obj_opt_idx].label[0], self.objects[to_idx].label[0]
+                    self.objects[obj_opt_idx].label[0],
+                    self.objects[to_idx].label[0]
                 )
             }
             (Some(obj_opt_idx), Some(obj_loc_idx), _, Some(player_loc_idx))
                 if obj_loc_idx == player_loc_idx =>
             {
-                format!("You pick up the {}.\n", self.objects[obj_opt_idx].label[0])
+                tr!(
+                    self,
+                    "You pick up the {}.\n",
+                    self.objects[obj_opt_idx].label[0]
+                )
             }
-            (Some(obj_opt_idx), Some(obj_loc_idx), _, _) => format!(
+            (Some(obj_opt_idx), Some(obj_loc_idx), _, _) => tr!(
+                self,
                 "You got {} from {}.\n",
-                self.objects[obj_opt_idx].label[0], self.objects[obj_loc_idx].label[0]
+                self.objects[obj_opt_idx].label[0],
+                self.objects[obj_loc_idx].label[0]
             ),
             // This arm should never get hit.
-            (None, _, _, _) | (_, None, _, _) => "Please you have to drop something.\n".to_string(),
+            (None, _, _, _) | (_, None, _, _) => tr!(self, "Please you have to drop something.\n"),
         }
     }
 
@@ -911,8 +4072,8 @@ impl World {
 
         match (obj_opt, obj_loc, to) {
             (None, _, _) => "".to_string(),
-            (Some(_), _, None) => "No one is present here to give.\n".to_string(),
-            (Some(_), None, Some(_)) => "You cannot get that!!\n".to_string(),
+            (Some(_), _, None) => tr!(self, "No one is present here to give.\n"),
+            (Some(_), None, Some(_)) => tr!(self, "You cannot get that!!\n"),
             (Some(obj_idx), Some(_), Some(to_idx)) => {
                 let output = self.describe_move(obj_opt, to);
                 self.objects[obj_idx].location = Some(to_idx);
@@ -953,39 +4114,43 @@ impl World {
         command: Command,
         noun: &String,
     ) -> (String, Option<usize>) {
+        let verb = self.catalog.word(&command);
         let object_held = self.object_index(noun, from, Distance::Held);
         let object_not_here = self.object_index(noun, from, Distance::NotHere);
 
         match (from, object_held, object_not_here) {
             (None, _, _) => (
-                format!("I don't understand what is needed {command}.\n"),
+                tr!(self, "I don't understand what is needed {}.\n", verb),
                 None,
             ),
             (Some(_), AmbiguousOption::None, AmbiguousOption::None) => (
-                format!("Please use correct command for: {}.\n", command),
+                tr!(self, "Please use correct command for: {}.\n", verb),
                 None,
             ),
             (Some(from), AmbiguousOption::None, _) if from == LOC_PLAYER => {
-                (format!("You are not holding any {}.\n", noun), None)
+                (tr!(self, "You are not holding any {}.\n", noun), None)
             }
             (Some(from), AmbiguousOption::Some(object), _) if object == from => (
-                format!(
+                tr!(
+                    self,
                     "It is illegal to do this: {}.\n",
                     self.objects[object].label[0]
                 ),
                 None,
             ),
             (Some(_), AmbiguousOption::Ambiguous, _) => (
-                format!(
+                tr!(
+                    self,
                     "Please be more specific about which {} you want to {}.\n",
-                    noun, command
+                    noun,
+                    verb
                 ),
                 None,
             ),
             (Some(_), AmbiguousOption::Some(object_held), _) => ("".to_string(), Some(object_held)),
             (Some(_), AmbiguousOption::None, AmbiguousOption::Some(_))
             | (Some(_), AmbiguousOption::None, AmbiguousOption::Ambiguous) => {
-                (format!("You don't see any {} here.\n", noun), None)
+                (tr!(self, "You don't see any {} here.\n", noun), None)
             }
         }
     }
@@ -1007,37 +4172,355 @@ impl World {
         player_loc
     }
 
-    pub fn display_help(&self) -> String {
-        "Available commands are\n
-        look\n
-        attack <enemy name>\n
-        go <location>\n
-        get <item name>\n
-        drop <item name>\n
-        inventory \n
-        map \n
-        quit\n
-        help\n"
-            .to_string()
+    /// Lists the commands that work right now, or explains one of them
+    pub fn display_help(&self, topic: &str) -> String {
+        if !topic.is_empty() {
+            let topic = topic.trim();
+            let first_word = topic.split_whitespace().next().unwrap_or_default();
+            let found = COMMANDS
+                .iter()
+                .find(|help| help.verbs.contains(&topic))
+                .or_else(|| {
+                    COMMANDS
+                        .iter()
+                        .find(|help| help.verbs.contains(&first_word))
+                });
+            return match found {
+                Some(help) => self.describe_command(help),
+                None => {
+                    tr!(self, "There is no command called '{}'.\n", topic)
+                        + &self.suggest_verb(topic)
+                }
+            };
+        }
+        let mut result = match self.mode {
+            Mode::Exploring => tr!(self, "Available commands are:\n"),
+            Mode::Fighting(_) => tr!(self, "You are in a fight. You can use:\n"),
+            Mode::Talking { .. } => tr!(self, "You are in a conversation. You can use:\n"),
+        };
+        for help in COMMANDS.iter().filter(|help| help.available(&self.mode)) {
+            result += &format!(
+                "  {:<28} {}\n",
+                tr!(self, help.usage),
+                tr!(self, help.description)
+            );
+        }
+        result
+            + &tr!(
+                self,
+                "\nSeparate commands with ',', ';' or 'then' to give several at once, e.g. 'get sword, go west then look'. Add 'x3' to 'use' to strike three times.\n"
+            )
+            + &tr!(
+                self,
+                "Type 'help <command>' to learn more about a command.\n"
+            )
     }
 
-    pub fn display_locations(&self) -> String {
-        let mut result = String::new();
-        result += "Available locations:\n";
-        let mut destinations = std::collections::HashSet::new();
+    /// Shows how to use a command, with examples
+    fn describe_command(&self, help: &CommandHelp) -> String {
+        let mut result = format!(
+            "{}\n{}\n",
+            tr!(self, help.usage),
+            tr!(self, help.description)
+        );
+        if help.verbs.len() > 1 {
+            result += &tr!(self, "Also: {}\n", help.verbs[1..].join(", "));
+        }
+        if !help.available(&self.mode) {
+            result += &tr!(self, "You can't use it right now.\n");
+        }
+        if !help.examples.is_empty() {
+            result += &tr!(self, "Examples:\n");
+            for example in help.examples {
+                result += &format!("  {}\n", tr!(self, example));
+            }
+        }
+        result
+    }
 
-        for object in &self.objects {
-            if let Some(destination) = object.destination {
-                destinations.insert(destination);
+    /// Answers a command the game doesn't know, pointing at the closest one that works
+    fn unknown_command(&self, input: &str) -> String {
+        tr!(self, "Invalid command!!\n")
+            + &self.suggest_verb(input.split_whitespace().next().unwrap_or_default())
+            + &tr!(self, "Type 'help' to see the commands you can use.\n")
+    }
+
+    /// Suggests the command word closest to a misspelt one, if any is close enough
+    fn suggest_verb(&self, word: &str) -> String {
+        let word = word.to_lowercase();
+        let closest = COMMANDS
+            .iter()
+            .filter(|help| help.available(&self.mode))
+            .flat_map(|help| help.verbs.iter())
+            .map(|verb| (edit_distance(&word, verb), *verb))
+            .filter(|&(distance, verb)| distance <= 2 && distance < verb.chars().count())
+            .min_by_key(|&(distance, _)| distance);
+        match closest {
+            Some((_, verb)) => tr!(self, "Did you mean '{}'?\n", verb),
+            None => String::new(),
+        }
+    }
+
+    /// The words tab completion offers right now: the commands of the current mode, also in
+    /// the player's language, and the names of everything the player can see
+    pub fn completions(&self) -> Completions {
+        let mut verbs: Vec<String> = Vec::new();
+        for help in COMMANDS.iter().filter(|help| help.available(&self.mode)) {
+            for verb in help.verbs.iter().filter(|verb| !verb.starts_with('<')) {
+                let phrases = VERB_PHRASES
+                    .iter()
+                    .filter(|phrase| phrase.split(' ').next() == Some(verb));
+                for english in std::iter::once(verb).chain(phrases) {
+                    verbs.push(english.to_string());
+                    verbs.extend(
+                        self.catalog
+                            .vocabulary
+                            .iter()
+                            .filter(|(_, word)| word == english)
+                            .map(|(word, _)| word.to_lowercase()),
+                    );
+                }
+            }
+        }
+        let mut labels: Vec<String> = Vec::new();
+        for (position, object) in self.objects.iter().enumerate() {
+            let distance = self.get_distance(Some(LOC_PLAYER), Some(position));
+            if object.despawned || distance == Distance::Player || distance > Distance::OverThere {
+                continue;
+            }
+            for label in &object.label {
+                // Leave out notes like "(enemy)", the name alone is enough
+                let name = label.split(" (").next().unwrap_or_default().trim();
+                if !name.is_empty() {
+                    labels.push(name.to_lowercase());
+                }
+            }
+        }
+        verbs.sort();
+        verbs.dedup();
+        labels.sort();
+        labels.dedup();
+        Completions { verbs, labels }
+    }
+
+    /// Checks if the player has been to a location. The current location always counts.
+    pub fn is_visited(&self, location: usize) -> bool {
+        self.objects[location].visited || self.objects[LOC_PLAYER].location == Some(location)
+    }
+
+    /// Lays out the explored locations on a grid following the compass directions of the passages
+    fn map_layout(&self) -> HashMap<usize, (i64, i64)> {
+        let mut positions: HashMap<usize, (i64, i64)> = HashMap::new();
+        let start = match self.objects[LOC_PLAYER].location {
+            Some(start) => start,
+            None => return positions,
+        };
+        let mut queue = VecDeque::from([start]);
+        positions.insert(start, (0, 0));
+
+        while let Some(location) = queue.pop_front() {
+            let (x, y) = positions[&location];
+            for (pos, passage) in self.objects.iter().enumerate() {
+                if !self.is_discovered(pos) {
+                    continue;
+                }
+                let (destination, (dx, dy)) = match (
+                    passage.location,
+                    passage.destination,
+                    compass_offset(&passage.label[0]),
+                ) {
+                    (Some(from), Some(to), Some(offset)) if from == location => (to, offset),
+                    _ => continue,
+                };
+                let position = (x + dx, y + dy);
+                if positions.contains_key(&destination)
+                    || positions.values().any(|p| *p == position)
+                {
+                    continue;
+                }
+                positions.insert(destination, position);
+                queue.push_back(destination);
+            }
+        }
+        positions
+    }
+
+    /// Draws an ASCII map of the locations the player has explored
+    pub fn display_map(&self) -> String {
+        let positions = self.map_layout();
+        if positions.is_empty() {
+            return tr!(self, "You have no idea where you are.\n");
+        }
+        let player_loc = self.objects[LOC_PLAYER].location;
+        let cell_text = |location: usize| -> String {
+            if Some(location) == player_loc {
+                format!("[*{}*]", self.objects[location].label[0])
+            } else if self.is_visited(location) {
+                format!("[{}]", self.objects[location].label[0])
+            } else {
+                "[???]".to_string()
+            }
+        };
+        let width = positions
+            .keys()
+            .map(|&loc| cell_text(loc).len())
+            .max()
+            .unwrap_or(0);
+        let min_x = positions.values().map(|p| p.0).min().unwrap_or(0);
+        let max_x = positions.values().map(|p| p.0).max().unwrap_or(0);
+        let min_y = positions.values().map(|p| p.1).min().unwrap_or(0);
+        let max_y = positions.values().map(|p| p.1).max().unwrap_or(0);
+        let at = |x: i64, y: i64| {
+            positions
+                .iter()
+                .find(|(_, p)| **p == (x, y))
+                .map(|(loc, _)| *loc)
+        };
+        // Two neighbouring cells are joined when the player knows of a passage between them
+        let joined = |a: Option<usize>, b: Option<usize>| match (a, b) {
+            (Some(a), Some(b)) => [
+                self.passage_index(Some(a), Some(b)),
+                self.passage_index(Some(b), Some(a)),
+            ]
+            .into_iter()
+            .flatten()
+            .any(|passage| self.is_discovered(passage)),
+            _ => false,
+        };
+
+        let mut result = String::new();
+        for y in min_y..=max_y {
+            let mut row = String::new();
+            let mut below = String::new();
+            for x in min_x..=max_x {
+                let cell = at(x, y).map(cell_text).unwrap_or_default();
+                let left_pad = (width - cell.len()) / 2;
+                let right_pad = width - cell.len() - left_pad;
+                // Stretch the connectors up to the cell's brackets
+                let left_fill = if joined(at(x - 1, y), at(x, y)) {
+                    "-"
+                } else {
+                    " "
+                };
+                let right_fill = if joined(at(x, y), at(x + 1, y)) {
+                    "-"
+                } else {
+                    " "
+                };
+                row += &(left_fill.repeat(left_pad) + &cell + &right_fill.repeat(right_pad));
+                below += &format!(
+                    "{:^width$}",
+                    if joined(at(x, y), at(x, y + 1)) {
+                        "|"
+                    } else {
+                        ""
+                    },
+                    width = width
+                );
+                if x < max_x {
+                    row += if joined(at(x, y), at(x + 1, y)) {
+                        "---"
+                    } else {
+                        "   "
+                    };
+                    below += "   ";
+                }
+            }
+            result += row.trim_end();
+            result += "\n";
+            if y < max_y {
+                result += below.trim_end();
+                result += "\n";
             }
         }
+        result
+            + &tr!(
+                self,
+                "\n* marks your location, ??? a place you haven't been to yet\n"
+            )
+    }
+}
+
+/// Returns the grid offset of a compass direction, north being up
+fn compass_offset(direction: &str) -> Option<(i64, i64)> {
+    match direction.to_lowercase().as_str() {
+        "north" => Some((0, -1)),
+        "south" => Some((0, 1)),
+        "east" => Some((1, 0)),
+        "west" => Some((-1, 0)),
+        _ => None,
+    }
+}
+
+impl HighScores {
+    /// How many runs are kept for every world file
+    pub const KEPT_PER_WORLD: usize = 5;
 
-        for (index, object) in self.objects.iter().enumerate() {
-            //let location = &self.objects[locations];
-            if destinations.contains(&index) {
-                //println!("{}: {}", index, object.label[0]);
-                result += &format!("{}: {}\n", index, object.label[0]);
+    /// Reads the high score table. A missing file is an empty table.
+    pub fn read_from_file(high_score_file: &str) -> Result<HighScores, std::io::Error> {
+        match read_to_string(Path::new(high_score_file)) {
+            Ok(data) => {
+                ron::from_str(&data).map_err(|de_err| std::io::Error::other(de_err.to_string()))
             }
+            Err(file_err) if file_err.kind() == io::ErrorKind::NotFound => {
+                Ok(HighScores::default())
+            }
+            Err(file_err) => Err(file_err),
+        }
+    }
+
+    /// Writes the high score table
+    pub fn save_to_file(&self, high_score_file: &str) -> Result<(), std::io::Error> {
+        let serialized_data = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|ser_err| std::io::Error::other(ser_err.to_string()))?;
+        write(Path::new(high_score_file), serialized_data)
+    }
+
+    /// Returns the best runs for a world file, best first
+    pub fn top(&self, world_file: &str) -> Vec<&HighScore> {
+        let mut top: Vec<&HighScore> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.world_file == world_file)
+            .collect();
+        top.sort_by(|a, b| b.score.cmp(&a.score).then(a.turns.cmp(&b.turns)));
+        top.truncate(Self::KEPT_PER_WORLD);
+        top
+    }
+
+    /// Adds a run and drops the ones that no longer make the table.
+    /// Returns the place the run got, if it made it.
+    pub fn add(&mut self, entry: HighScore) -> Option<usize> {
+        let world_file = entry.world_file.clone();
+        let mut table: Vec<HighScore> = self.top(&world_file).into_iter().cloned().collect();
+        let place = table
+            .iter()
+            .position(|other| (entry.score, other.turns) > (other.score, entry.turns))
+            .unwrap_or(table.len());
+        table.insert(place, entry);
+        table.truncate(Self::KEPT_PER_WORLD);
+
+        self.entries.retain(|other| other.world_file != world_file);
+        self.entries.extend(table);
+        (place < Self::KEPT_PER_WORLD).then_some(place + 1)
+    }
+
+    /// The high score table of a world file
+    pub fn display(&self, world_file: &str, catalog: &Catalog) -> String {
+        let mut result = catalog.translate("\n===== High scores ({}) =====\n", &[&world_file]);
+        for (place, entry) in self.top(world_file).iter().enumerate() {
+            let turns = if entry.won {
+                "in {} turns (won)"
+            } else {
+                "in {} turns"
+            };
+            result += &format!(
+                "{}. {:<12} {:>6} {}\n",
+                place + 1,
+                entry.name,
+                entry.score,
+                catalog.translate(turns, &[&entry.turns])
+            );
         }
         result
     }
@@ -1053,6 +4536,40 @@ impl Default for World {
 /// Function that parses user's commands into a verb and a noun
 pub fn parse(input: String) -> Command {
     let input = input.to_lowercase();
+    let mut commands: Vec<Command> = Vec::new();
+    for part in input.split([',', ';']) {
+        let words: Vec<&str> = part.split_whitespace().collect();
+        let mut command: Vec<&str> = Vec::new();
+        for (position, &word) in words.iter().enumerate() {
+            // "then" only separates two whole commands, so it can still be part of a name
+            let separates = word == "then"
+                && !command.is_empty()
+                && words.get(position + 1).is_some_and(|next| {
+                    !matches!(parse_command(next.to_string()), Command::Unknown(_))
+                });
+            if separates {
+                commands.push(parse_command(command.join(" ")));
+                command.clear();
+            } else {
+                command.push(word);
+            }
+        }
+        if !command.is_empty() {
+            commands.push(parse_command(command.join(" ")));
+        }
+    }
+    match commands.len() {
+        0 => parse_command(input),
+        1 => commands.remove(0),
+        _ => Command::Chain(commands),
+    }
+}
+
+/// Most times a command can be repeated with "x<times>" in one go
+pub const MAX_REPEAT: usize = 10;
+
+/// Parses a single command, without "then" or commas
+fn parse_command(input: String) -> Command {
     let mut split_input = input.split_whitespace();
 
     let verb = split_input.next().unwrap_or_default().to_string();
@@ -1065,30 +4582,159 @@ pub fn parse(input: String) -> Command {
     });
 
     match verb.as_str() {
-        "look" => Command::Look(noun),
-        "go" => Command::Go(noun),
+        "look" | "examine" => Command::Look(noun.strip_prefix("at ").unwrap_or(&noun).to_string()),
+        "go" => match noun.strip_prefix("to ") {
+            Some(location) => Command::Travel(location.to_string()),
+            None => Command::Go(noun),
+        },
+        "travel" => Command::Travel(noun),
         "quit" => Command::Quit,
         "attack" => Command::Attack(noun),
         "drop" => Command::Drop(noun),
         "get" => Command::Get(noun),
-        "help" => Command::Help,
+        "pick" => Command::Get(noun.strip_prefix("up ").unwrap_or(&noun).to_string()),
+        "talk" => Command::Talk(noun.strip_prefix("to ").unwrap_or(&noun).to_string()),
+        "help" => Command::Help(noun),
         "inventory" => Command::Inventory,
+        "quests" | "journal" => Command::Quests,
+        "recipes" => Command::Recipes,
+        "combine" => Command::Combine(noun),
+        "craft" => Command::Craft(noun),
+        "buy" => Command::Buy(noun),
+        "sell" => Command::Sell(noun),
+        "list" => Command::List,
+        "equip" => Command::Equip(noun),
+        "repair" | "fix" => Command::Repair(noun),
+        "save" => Command::Save(noun),
+        "load" => Command::Load(noun),
+        "status" | "hud" => Command::Status,
+        "stats" | "score" => Command::Stats,
+        "eat" | "drink" => Command::Consume(noun),
         "map" => Command::Map,
+        "use" => match noun.rsplit_once(' ') {
+            Some((weapon, times)) if times.starts_with('x') => match times[1..].parse::<usize>() {
+                Ok(times) if times > 0 => Command::Chain(vec![
+                    Command::Use(weapon.to_string());
+                    times.min(MAX_REPEAT)
+                ]),
+                _ => Command::Use(noun),
+            },
+            _ => Command::Use(noun),
+        },
+        "again" | "g" => Command::Again,
+        "run" | "flee" => Command::Run,
+        "bye" | "leave" => Command::Leave,
+        _ if noun.is_empty() && verb.parse::<usize>().is_ok() => {
+            Command::Choose(verb.parse().unwrap())
+        }
         _ => Command::Unknown(input.trim().to_string()),
     }
 }
 
-/// Function that takes user's input
-pub fn get_input() -> Command {
-    print!("\n> ");
-    io::stdout().flush().unwrap();
+/// Verbs with the word the parser skips after them, like "at" in "look at sword". They
+/// are completed like verbs of their own, so names can follow them.
+const VERB_PHRASES: &[&str] = &["look at", "talk to"];
+
+/// The words that can finish what the player has typed so far
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    pub verbs: Vec<String>,
+    pub labels: Vec<String>,
+}
+
+impl Completions {
+    /// Every way to finish the line: a command word at the start, or a name after a
+    /// command word or after "with"
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        let typed = line.trim_start().to_lowercase();
+        let mut result: Vec<String> = self
+            .verbs
+            .iter()
+            .filter(|verb| verb.starts_with(&typed))
+            .cloned()
+            .collect();
+        for verb in &self.verbs {
+            let rest = match typed.strip_prefix(verb.as_str()) {
+                Some(rest) if rest.starts_with(' ') => rest,
+                _ => continue,
+            };
+            let start = typed.len() - rest.len() + rest.rfind(" with ").map_or(1, |with| with + 6);
+            let (head, name) = typed.split_at(start);
+            result.extend(
+                self.labels
+                    .iter()
+                    .filter(|label| label.starts_with(name))
+                    .map(|label| format!("{}{}", head, label)),
+            );
+        }
+        result.sort();
+        result.dedup();
+        result
+    }
+}
+
+/// Offers the completions to the line editor
+pub struct InputHelper(pub Completions);
 
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read input");
+impl Completer for InputHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok((0, self.0.complete(&line[..pos])))
+    }
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Validator for InputHelper {}
+
+impl Helper for InputHelper {}
+
+/// Reads commands with line editing, history and tab completion
+pub type LineEditor = Editor<InputHelper, DefaultHistory>;
+
+/// Makes a line editor that keeps the last few hundred commands
+pub fn new_line_editor() -> LineEditor {
+    let config = Config::builder()
+        .max_history_size(500)
+        .and_then(|config| config.history_ignore_dups(true))
+        .expect("Invalid line editor settings")
+        .auto_add_history(true)
+        .build();
+    Editor::with_config(config).expect("Failed to start the line editor")
+}
+
+/// Function that takes user's input, in the language of the catalog. Tab completes the
+/// command words and the names of what the player can see.
+pub fn get_input(editor: &mut LineEditor, world: &World) -> Command {
+    println!();
+    editor.set_helper(Some(InputHelper(world.completions())));
+    loop {
+        match editor.readline("> ") {
+            Ok(input) => return parse(world.catalog.to_english(&input)),
+            // Resizing the window interrupts the prompt, but the player isn't done yet
+            Err(ReadlineError::WindowResized) => continue,
+            // Ctrl-C, Ctrl-D, the end of piped input and a terminal that can't be read
+            // from all mean the player is done
+            Err(_) => return Command::Quit,
+        }
+    }
+}
 
-    parse(input)
+/// Reads the answer to a question, like the difficulty or the player's name, with the
+/// same line editing and history as commands. None means the player closed the input.
+pub fn read_answer(editor: &mut LineEditor) -> Option<String> {
+    editor.set_helper(None);
+    editor.readline("> ").ok()
 }
 
 /// Function to update the screen
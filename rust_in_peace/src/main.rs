@@ -1,22 +1,69 @@
 use clearscreen::clear;
 use regex::Regex;
-use std::io::{stdout, Write};
+use std::io::{stdout, IsTerminal, Write};
+use std::println;
 use std::process::exit;
 use std::thread;
 use std::time::Duration;
-use std::{io, println};
 
 const GAME_FILE_LOCATION: &str = "./game_file.ron";
+const HIGH_SCORE_FILE_LOCATION: &str = "./highscores.ron";
+const HISTORY_FILE_LOCATION: &str = "./history.txt";
 
 pub mod game_lib;
 
+/// Options given on the command line
+struct Args {
+    difficulty: Option<game_lib::Difficulty>,
+    language: String,
+}
+
 fn main() {
+    let args = match cli_args() {
+        Ok(args) => args,
+        Err(arg_err) => {
+            println!("Error: {}", arg_err);
+            println!("Usage: main [--difficulty easy|normal|hard|custom:<enemy damage>,<enemy health>,<healing>,<dodge>] [--lang <language>]");
+            return;
+        }
+    };
+    let catalog = match game_lib::Catalog::load(&args.language) {
+        Ok(catalog) => catalog,
+        Err(file_err) => {
+            println!(
+                "Error: could not load language '{}': {}",
+                args.language, file_err
+            );
+            return;
+        }
+    };
     let world_result = init_game(GAME_FILE_LOCATION);
 
     match world_result {
-        Ok(world) => {
-            // Here we will run the game
-            do_game(world);
+        Ok(mut world) => {
+            world.catalog = catalog;
+            let mut editor = game_lib::new_line_editor();
+            // There is no history yet the first time the game is played
+            let _ = editor.load_history(HISTORY_FILE_LOCATION);
+            // Here we will run the game, and a fresh one for as long as the player wants more
+            loop {
+                let catalog = world.catalog.clone();
+                if !do_game(world, args.difficulty, &mut editor) {
+                    break;
+                }
+                clear().expect("Failed to clear screen");
+                match init_game(GAME_FILE_LOCATION) {
+                    Ok(new_world) => {
+                        world = new_world;
+                        world.catalog = catalog;
+                    }
+                    Err(file_err) => {
+                        println!("Error: {}", file_err);
+                        println!("{}", catalog.word("Goodbye!"));
+                        break;
+                    }
+                }
+            }
         }
         Err(file_err) => {
             println!("Error: {}", file_err);
@@ -29,80 +76,191 @@ fn init_game(file_location: &str) -> Result<game_lib::World, std::io::Error> {
     game_lib::World::read_from_file(file_location)
 }
 
-fn do_game(mut world: game_lib::World) {
+/// Reads the difficulty and the language from the command line
+fn cli_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let mut difficulty = None;
+    let mut language = "en".to_string();
+
+    while let Some(arg) = args.next() {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if name != "--difficulty" && name != "--lang" {
+            return Err(format!("Unknown argument '{}'", name));
+        }
+        let value = match value {
+            Some(value) => value,
+            None => args.next().ok_or(format!("{} needs a value", name))?,
+        };
+        if name == "--difficulty" {
+            let english = game_lib::Catalog::default();
+            difficulty = Some(
+                value
+                    .parse()
+                    .map_err(|parse_err: String| english.translate(&parse_err, &[&value]))?,
+            );
+        } else {
+            language = value;
+        }
+    }
+    Ok(Args {
+        difficulty,
+        language,
+    })
+}
+
+/// Asks the player how hard they want the game to be
+fn choose_difficulty(
+    catalog: &game_lib::Catalog,
+    editor: &mut game_lib::LineEditor,
+) -> game_lib::Difficulty {
+    loop {
+        println!(
+            "{}",
+            catalog.word("Choose a difficulty: easy, normal or hard (default normal)")
+        );
+
+        // Closing the input picks the default, like an empty answer
+        let answer = game_lib::read_answer(editor).unwrap_or_default();
+        match catalog.to_english(&answer).parse() {
+            Ok(difficulty) => return difficulty,
+            Err(parse_err) => println!("{}", catalog.translate(&parse_err, &[&answer.trim()])),
+        }
+    }
+}
+
+/// Plays one game. Returns whether the player wants to play another one.
+fn do_game(
+    mut world: game_lib::World,
+    cli_difficulty: Option<game_lib::Difficulty>,
+    editor: &mut game_lib::LineEditor,
+) -> bool {
+    // The status bar only makes sense when someone is looking at a terminal
+    world.show_status = stdout().is_terminal();
     clear().expect("Failed to clear screen");
-    println!("Hello, Player!\n");
-    println!("Welcome to Rust In Peace\n");
-    println!("Would you like to start the game? (Y/N)");
+    let catalog = &world.catalog;
+    println!("{}", catalog.word("Hello, Player!\n"));
+    println!("{}", catalog.word("Welcome to Rust In Peace\n"));
+    println!(
+        "{}",
+        catalog.word("Would you like to start the game? (Y/N)")
+    );
 
-    //https://www.geeksforgeeks.org/standard-i-o-in-rust/
-    let mut answer = String::new();
-    io::stdin()
-        .read_line(&mut answer)
-        .expect("Failed to read input");
+    // Closing the input counts as a no
+    let answer = game_lib::read_answer(editor).unwrap_or_else(|| "n".to_string());
 
     //https://docs.rs/regex/latest/regex/
     let no = Regex::new("[nN]|[nN][oO]").unwrap();
 
     //https://doc.rust-lang.org/std/primitive.str.html#method.trim
     if no.is_match(answer.trim()) {
-        println!("Goodbye!");
+        println!("{}", catalog.word("Goodbye!"));
         std::process::exit(0);
     }
 
+    let difficulty = cli_difficulty.unwrap_or_else(|| choose_difficulty(&world.catalog, editor));
+    world.set_difficulty(difficulty);
+
     //https://docs.rs/clearscreen/latest/clearscreen/
     clear().expect("Failed to clear screen");
 
     let message="You find yourself lost in a gloomy forest. You see a column of smoke rising in the sky. It seems to be very far away.\n";
-    for c in message.chars() {
+    for c in world.catalog.word(message).chars() {
         print!("{}", c);
         stdout().flush().unwrap(); // Flush the output to make it appear immediately
         thread::sleep(Duration::from_millis(25)); // Delay between characters
     }
 
-    println!("Hint: Enter <help> to display the commands available");
+    println!(
+        "{}",
+        world
+            .catalog
+            .word("Hint: Enter <help> to display the commands available")
+    );
     let mut command: game_lib::Command;
     //let mut world = game_lib::World::new();
-    let mut output: String;
+    let mut events: Vec<game_lib::Event>;
 
     // Main game loop
     loop {
         if world.game_over() {
-            println!("\nWould you like to play again?");
-            println!("\n> ");
-            io::stdout().flush().unwrap();
-
-            let mut command = String::new();
-            io::stdin()
-                .read_line(&mut command)
-                .expect("Failed to read input");
-            if command.trim().to_lowercase() == "y" {
-                clear().expect("Failed to clear screen");
-                let world_result = init_game(GAME_FILE_LOCATION);
+            println!("{}", world.display_summary());
+            record_high_score(&world, editor);
 
-                match world_result {
-                    Ok(world) => {
-                        // Here we will run the game
-                        do_game(world);
-                    }
-                    Err(file_err) => {
-                        println!("Error: {}", file_err);
-                        break;
-                    }
-                }
-            } else {
-                break;
+            println!("{}", world.catalog.word("\nWould you like to play again?"));
+
+            let answer = game_lib::read_answer(editor).unwrap_or_default();
+            if world.catalog.to_english(&answer) == "y" {
+                return true;
             }
+            break;
+        }
+        command = game_lib::get_input(editor, &world);
+        // Losing the history is not worth interrupting the game for
+        let _ = editor.save_history(HISTORY_FILE_LOCATION);
+        events = world.update_state(&command);
+        game_lib::update_screen(world.render(&events));
+        if world.show_status && stdout().is_terminal() {
+            println!("\n{}", world.status_line());
         }
-        command = game_lib::get_input();
-        output = world.update_state(&command);
-        game_lib::update_screen(output);
 
-        if matches!(command, game_lib::Command::Quit) {
-            println!("\nGoodbye!");
+        if events.contains(&game_lib::Event::Quit) {
+            println!("{}", world.display_summary());
+            println!("\n{}", world.catalog.word("Goodbye!"));
             exit(0);
         }
     }
 
-    println!("Goodbye!");
+    println!("{}", world.catalog.word("Goodbye!"));
+    false
+}
+
+fn record_high_score(world: &game_lib::World, editor: &mut game_lib::LineEditor) {
+    let catalog = &world.catalog;
+    println!(
+        "{}",
+        catalog.word("\nEnter your name for the high score table:")
+    );
+
+    let name = game_lib::read_answer(editor).unwrap_or_default();
+    let name = match name.trim() {
+        "" => catalog.word("Player"),
+        name => name.to_string(),
+    };
+
+    let mut high_scores = match game_lib::HighScores::read_from_file(HIGH_SCORE_FILE_LOCATION) {
+        Ok(high_scores) => high_scores,
+        Err(file_err) => {
+            println!(
+                "{}",
+                catalog.translate("Could not read the high scores: {}", &[&file_err])
+            );
+            return;
+        }
+    };
+    let place = high_scores.add(game_lib::HighScore {
+        name,
+        world_file: GAME_FILE_LOCATION.to_string(),
+        score: world.score(),
+        turns: world.turn,
+        won: !world.health(game_lib::LOC_PLAYER).is_dead(),
+    });
+    if let Some(place) = place {
+        println!(
+            "{}",
+            catalog.translate(
+                "\nYou made it to number {} on the high score table!",
+                &[&place]
+            )
+        );
+    }
+    if let Err(file_err) = high_scores.save_to_file(HIGH_SCORE_FILE_LOCATION) {
+        println!(
+            "{}",
+            catalog.translate("Could not save the high scores: {}", &[&file_err])
+        );
+    }
+    println!("{}", high_scores.display(GAME_FILE_LOCATION, catalog));
 }
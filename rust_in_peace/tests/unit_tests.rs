@@ -1,5 +1,12 @@
 use game_lib::*;
 
+// Indices of the items `World::new` starts with, after the locations and enemies
+const SWORD: usize = 10;
+const BOW: usize = 11;
+const BONES: usize = 12;
+const SPEAR: usize = 13;
+const APPLE: usize = 14;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -10,35 +17,50 @@ mod tests {
         let mut world = World::new();
 
         // Scenario 1: Player's health is 0
-        world.objects[LOC_PLAYER].health = Some(0);
+        world.objects[LOC_PLAYER].health = Some(Health(0));
         assert!(world.game_over());
 
         // Scenario 2: Player's health is not 0, but all enemies' health is 0
-        world.objects[LOC_PLAYER].health = Some(100);
-        world.objects[LOC_BEAR].health = Some(0);
-        world.objects[LOC_TROLL].health = Some(0);
-        world.objects[LOC_BANDITS].health = Some(0);
+        world.objects[LOC_PLAYER].health = Some(Health(100));
+        world.objects[LOC_BEAR].health = Some(Health(0));
+        world.objects[LOC_TROLL].health = Some(Health(0));
+        world.objects[LOC_BANDITS].health = Some(Health(0));
         assert!(world.game_over());
 
         // Scenario 3: Neither player's health is 0 nor all enemies' health is 0
-        world.objects[LOC_PLAYER].health = Some(100);
-        world.objects[LOC_BEAR].health = Some(100);
-        world.objects[LOC_TROLL].health = Some(100);
-        world.objects[LOC_BANDITS].health = Some(100);
+        world.objects[LOC_PLAYER].health = Some(Health(100));
+        world.objects[LOC_BEAR].health = Some(Health(100));
+        world.objects[LOC_TROLL].health = Some(Health(100));
+        world.objects[LOC_BANDITS].health = Some(Health(100));
         assert!(!world.game_over());
     }
 
+    #[test]
+    fn test_item_indices() {
+        // The other tests rely on these, so a reordered world shows up here first
+        let world = World::new();
+        for (index, label) in [
+            (SWORD, "Sword"),
+            (BOW, "Bow"),
+            (BONES, "Bones"),
+            (SPEAR, "Spear"),
+            (APPLE, "Apple"),
+        ] {
+            assert_eq!(world.objects[index].label[0], label);
+        }
+    }
+
     #[test]
     fn test_update_state() {
         let mut world = World::new();
         // Test case 1: Quit command
         let command = Command::Quit;
-        let result = world.update_state(&command);
+        let result = world.play(&command);
         assert_eq!(result, "Quitting.\nThank you for playing!"); // Checking if the result matches the expected message
 
         // Test case 2: Unknown command
         let command = Command::Unknown("InvalidCommand".to_string());
-        let result = world.update_state(&command);
+        let result = world.play(&command);
         assert!(result.contains("Invalid command!!")); // Checking if the result contains the expected message
     }
 
@@ -80,30 +102,38 @@ mod tests {
         let mut world = World::default();
 
         // Create the objects needed for the test
-        let player_health = Some(80);
+        let player_health = Some(Health(80));
 
         // Set the initial world state
         world.objects[LOC_PLAYER].health = player_health;
         world.objects.push(Object {
             label: vec!["Apple".to_string()],
             description: "An apple (Get it to increase health)".to_string(),
-            location: Some(LOC_TAVERN),
+            location: Some(LOC_PLAYER),
             destination: None,
             item: Some(true),
             enemy: false,
-            health: Some(10),
+            health: Some(Health(10)),
             attack: None,
             consumable: Some(true),
+            ..Default::default()
         });
 
         // Test consuming an object
-        let result = world.do_consume(Some(world.objects.len() - 1));
-
+        let apple = world.objects.len() - 1;
+        let events = world.update_state(&Command::Consume("apple".to_string()));
         assert_eq!(
-            result,
+            events,
+            vec![Event::ItemConsumed {
+                item: apple,
+                health: 90
+            }]
+        );
+        assert_eq!(
+            world.render(&events),
             "You have consumed the item. Your health has increased to 90\n"
         );
-        assert_eq!(world.objects[LOC_PLAYER].health, Some(90));
+        assert_eq!(world.objects[LOC_PLAYER].health, Some(Health(90)));
         assert_eq!(world.objects[world.objects.len() - 1].location, None);
     }
 
@@ -124,6 +154,7 @@ mod tests {
             health: None,
             attack: Some(20),
             consumable: Some(false),
+            ..Default::default()
         });
 
         // Test dropping an object
@@ -152,15 +183,1144 @@ mod tests {
     }
 
     #[test]
-    fn test_display_locations() {
+    fn test_choose_dialogue() {
         let mut world = World::default();
 
-        // Set up the initial world state
-        world.objects[LOC_PLAYER].location = Some(LOC_PLAYER);
+        // An NPC in the forest holding a gift for the player
+        let npc_index = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Hermit".to_string()],
+            description: "An old hermit".to_string(),
+            location: Some(LOC_FOREST),
+            npc: true,
+            dialogue: Some(vec![DialogueNode {
+                text: "Hello there.".to_string(),
+                options: vec![
+                    DialogueOption {
+                        text: "Can you help me?".to_string(),
+                        next: None,
+                        effects: vec![
+                            DialogueEffect::GiveItem(npc_index + 1),
+                            DialogueEffect::SetFlag("helped".to_string()),
+                            DialogueEffect::StartQuest("hermit".to_string()),
+                        ],
+                        unless_flag: Some("helped".to_string()),
+                        ..Default::default()
+                    },
+                    DialogueOption {
+                        text: "Goodbye.".to_string(),
+                        ..Default::default()
+                    },
+                ],
+            }]),
+            ..Default::default()
+        });
+        world.objects.push(Object {
+            label: vec!["Charm".to_string()],
+            description: "A lucky charm.".to_string(),
+            location: Some(npc_index),
+            item: Some(true),
+            ..Default::default()
+        });
+
+        world.quests.push(Quest {
+            id: "hermit".to_string(),
+            name: "The hermit's errand".to_string(),
+            description: "Help the hermit.".to_string(),
+            objectives: vec![Objective::Reach(LOC_TAVERN)],
+            ..Default::default()
+        });
+
+        assert_eq!(world.dialogue_options(npc_index, 0), vec![0, 1]);
+
+        let (result, next) = world.choose_dialogue(npc_index, 0, 0);
+        assert_eq!(
+            result,
+            "\nThe Hermit gives you the Charm.\n\nNew quest: The hermit's errand\nHelp the hermit.\n"
+        );
+        assert_eq!(next, None);
+        assert_eq!(world.objects[npc_index + 1].location, Some(LOC_PLAYER));
+        assert!(world.flags.contains(&"helped".to_string()));
+
+        // The option disappears once its flag is set
+        assert_eq!(world.dialogue_options(npc_index, 0), vec![1]);
+
+        // A reply leading to a node that doesn't exist ends the conversation instead of panicking
+        world.objects[npc_index].dialogue.as_mut().unwrap()[0].options[1].next = Some(5);
+        assert!(world.check_dialogue().is_err());
+        world.objects[npc_index].location = world.objects[LOC_PLAYER].location;
+        world.play(&Command::Talk("hermit".to_string()));
+        let result = world.play(&Command::Choose(1));
+        assert!(result.contains("The Hermit has nothing more to say."));
+        assert_eq!(world.mode, Mode::Exploring);
+    }
+
+    #[test]
+    fn test_advance_quests() {
+        let mut world = World::default();
+        let reward_index = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Medal".to_string()],
+            description: "A shiny medal.".to_string(),
+            item: Some(true),
+            ..Default::default()
+        });
+        world.quests.push(Quest {
+            id: "hunt".to_string(),
+            name: "The hunt".to_string(),
+            description: "Hunt down the bear.".to_string(),
+            objectives: vec![Objective::Reach(LOC_TAVERN), Objective::Defeat(LOC_BEAR)],
+            reward: QuestReward {
+                items: vec![reward_index],
+                ..Default::default()
+            },
+            status: QuestStatus::Active,
+            progress: 0,
+        });
+
+        // Going to the tavern completes the first objective
+        let result = world.do_go(&"North".to_string());
+        assert!(!result.contains("Quest completed"));
+        assert_eq!(world.quests[0].progress, 1);
+        assert!(world.do_quests().contains("Defeat the Bear"));
+
+        // Defeating the bear completes the quest and hands out the reward
+        world.objects[LOC_BEAR].health = Some(Health(0));
+        let result = world.advance_quests(None);
+        assert_eq!(
+            result,
+            "\nQuest completed: The hunt\nYou received the Medal.\n"
+        );
+        assert_eq!(world.quests[0].status, QuestStatus::Completed);
+        assert_eq!(world.objects[reward_index].location, Some(LOC_PLAYER));
+    }
+
+    #[test]
+    fn test_read_from_file() {
+        let world = World::read_from_file("./game_file.ron").unwrap();
+        assert_eq!(world.objects[LOC_PLAYER].label[0], "Player");
+        assert_eq!(world.quests[0].status, QuestStatus::Active);
+        assert!(world
+            .recipes
+            .iter()
+            .all(|recipe| world.templates.contains_key(&recipe.result)));
+        // Only weapons and enemies have an accuracy
+        assert!(world
+            .objects
+            .iter()
+            .all(|object| object.accuracy.is_none() || object.attack.is_some()));
+    }
+
+    #[test]
+    fn test_buy_and_sell() {
+        let mut world = World::default();
+        world.objects[LOC_PLAYER].gold = Some(30);
+
+        let merchant_index = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Peddler".to_string()],
+            description: "A travelling peddler".to_string(),
+            location: Some(LOC_FOREST),
+            npc: true,
+            merchant: true,
+            gold: Some(5),
+            ..Default::default()
+        });
+        world.objects.push(Object {
+            label: vec!["Dagger".to_string()],
+            description: "A sharp dagger.".to_string(),
+            location: Some(merchant_index),
+            item: Some(true),
+            attack: Some(15),
+            price: Some(20),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            world.do_list(),
+            "The Peddler has for sale:\nDagger - 20 gold\nYou have 30 gold.\n"
+        );
+
+        // Buying is reported like picking the item up
+        let events = world.update_state(&Command::Buy("dagger".to_string()));
+        assert_eq!(
+            events[0],
+            Event::ItemPickedUp {
+                item: merchant_index + 1,
+                from: merchant_index
+            }
+        );
+        assert_eq!(
+            world.render(&events),
+            "You got Dagger from Peddler.\nYou paid 20 gold. You have 10 gold left.\n"
+        );
+        assert_eq!(world.objects[merchant_index + 1].location, Some(LOC_PLAYER));
+        assert_eq!(world.objects[merchant_index].gold, Some(25));
+
+        // Selling it back only gets half the price
+        let result = world.do_sell(&"dagger".to_string());
+        assert_eq!(
+            result,
+            "You sold the Dagger to the Peddler for 10 gold. You now have 20 gold.\n"
+        );
+        assert_eq!(
+            world.objects[merchant_index + 1].location,
+            Some(merchant_index)
+        );
+        assert_eq!(world.objects[merchant_index].gold, Some(15));
+
+        world.objects[LOC_PLAYER].gold = Some(5);
+        assert_eq!(
+            world.do_buy(&"dagger".to_string()),
+            "The Dagger costs 20 gold, but you only have 5 gold.\n"
+        );
+    }
+
+    #[test]
+    fn test_do_examine() {
+        let mut world = World::new();
+        world.objects[LOC_BEAR].location = Some(LOC_FOREST);
+        world.objects[LOC_BEAR].health = Some(Health(60));
+        world.objects[LOC_BEAR].details = Some("It looks hungry.".to_string());
+
+        let result = world.do_look("bear");
+        assert_eq!(
+            result,
+            "Bear\nA bear (enemy)\nIt looks hungry.\nHealth: 60\nAttack: 20\n"
+        );
+
+        let result = world.do_look("north");
+        assert!(result.ends_with("It leads to the Tavern.\n"));
+
+        let result = world.do_look("sword");
+        assert_eq!(result, "You don't see any 'sword' here.\n");
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut world = World::new();
+        world.objects[LOC_PLAYER].health = Some(Health(80));
+        world.play(&Command::Go("north".to_string()));
+        assert_eq!(
+            world.status_line(),
+            "[Tavern] Health: 80/100 | Weapon: none | Turn: 1 | Score: 39"
+        );
+
+        // Toggling the status bar doesn't use up a turn
+        world.play(&Command::Status);
+        assert!(!world.show_status);
+        assert_eq!(world.turn, 1);
+
+        world.play(&Command::Get("bow".to_string()));
+        world.play(&Command::Equip("bow".to_string()));
+        assert_eq!(
+            world.status_line(),
+            "[Tavern] Health: 80/100 | Weapon: Bow | Turn: 3 | Score: 47"
+        );
+    }
+
+    #[test]
+    fn test_display_map() {
+        let mut world = World::new();
+        world.do_go(&"north".to_string());
+
+        let expected = "  [???]------[*Tavern*]-----[???]
+                 |
+              [Forest]
+
+* marks your location, ??? a place you haven't been to yet
+";
+        assert_eq!(world.display_map(), expected);
+        assert!(world.objects[LOC_FOREST].visited);
+        assert!(!world.is_visited(LOC_DUNGEONS));
+    }
+
+    #[test]
+    fn test_fog_of_war() {
+        let mut world = World::new();
+
+        let result = world.play(&Command::Go("north".to_string()));
+        assert!(result.starts_with("OK.\n You are in the Tavern\n"));
+        world.play(&Command::Go("south".to_string()));
+        let result = world.play(&Command::Go("north".to_string()));
+        assert!(result.starts_with("OK.\n You return to the Tavern.\n"));
+
+        // The passages out of the tavern are known, the ones out of the village are not
+        assert!(world.objects[LOC_TAVERN].visited);
+        assert!(!world.objects[LOC_VILLAGE].visited);
+        let village_north = world
+            .objects
+            .iter()
+            .position(|o| o.location == Some(LOC_VILLAGE) && o.destination == Some(LOC_STRONGHOLD))
+            .unwrap();
+        assert!(!world.is_discovered(village_north));
+
+        // Visited locations survive saving and loading
+        let save_file = std::env::temp_dir().join("rust_in_peace_fog_of_war.ron");
+        let save_file = save_file.to_str().unwrap();
+        world.save_to_file(save_file).unwrap();
+        let loaded = World::read_from_file(save_file).unwrap();
+        std::fs::remove_file(save_file).unwrap();
+        assert!(loaded.objects[LOC_FOREST].visited);
+        assert!(loaded.objects[LOC_TAVERN].visited);
+        assert!(!loaded.objects[LOC_VILLAGE].visited);
+        assert_eq!(loaded.objects[LOC_PLAYER].location, Some(LOC_TAVERN));
+        assert_eq!(loaded.display_map(), world.display_map());
+    }
+
+    #[test]
+    fn test_do_travel() {
+        let mut world = World::new();
+
+        // Unknown places can't be travelled to
+        let result = world.do_travel(&"village".to_string());
+        assert_eq!(result, "You don't know the way to the Village yet.\n");
+
+        world.do_go(&"north".to_string());
+        world.do_go(&"west".to_string());
+        world.do_go(&"tavern".to_string());
+        world.do_go(&"south".to_string());
+
+        // Place names are matched like object names, and every step of the way is a turn
+        world.effects.push(StatusEffect {
+            target: LOC_PLAYER,
+            kind: EffectKind::Poison,
+            turns_left: 5,
+            power: 1,
+        });
+        let turn = world.turn;
+        let health = world.health(LOC_PLAYER).value();
+        let result = world.play(&Command::Travel("villag".to_string()));
+        assert!(result.starts_with("You go north and pass through the Tavern.\n"));
+        assert_eq!(world.objects[LOC_PLAYER].location, Some(LOC_VILLAGE));
+        assert_eq!(world.turn, turn + 2);
+        assert_eq!(world.health(LOC_PLAYER).value(), health - 2);
+        world.effects.clear();
+
+        // An enemy on the way stops the journey
+        world.objects[LOC_TROLL].location = Some(LOC_TAVERN);
+        let result = world.play(&Command::Travel("forest".to_string()));
+        assert!(result.ends_with("You stop. The Troll (enemy) blocks your way.\n"));
+        assert_eq!(world.objects[LOC_PLAYER].location, Some(LOC_TAVERN));
+    }
+
+    #[test]
+    fn test_score() {
+        let mut world = World::new();
+        world.play(&Command::Go("north".to_string()));
+        world.play(&Command::Get("bow".to_string()));
+        world.stats.enemies_defeated = 1;
+        world.stats.damage_taken = 30;
+
+        // 100 for the enemy, 10 for the bow, 40 for two locations, -15 for damage, -2 for turns
+        assert_eq!(world.score(), 133);
+        assert!(world
+            .display_summary()
+            .contains("Damage taken          30     -15\n"));
+    }
+
+    #[test]
+    fn test_high_scores() {
+        let mut high_scores = HighScores::default();
+        let run = |name: &str, score: u64, turns: u64| HighScore {
+            name: name.to_string(),
+            world_file: "./game_file.ron".to_string(),
+            score,
+            turns,
+            won: false,
+        };
+
+        for score in [100, 300, 200, 50, 250] {
+            high_scores.add(run("Old", score, 40));
+        }
+        // Runs for other worlds are kept separately
+        high_scores.add(HighScore {
+            world_file: "./other.ron".to_string(),
+            ..run("Other", 1000, 10)
+        });
+
+        assert_eq!(high_scores.add(run("New", 250, 30)), Some(2));
+        assert_eq!(high_scores.add(run("Last", 10, 30)), None);
+
+        let top: Vec<(String, u64)> = high_scores
+            .top("./game_file.ron")
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.score))
+            .collect();
+        assert_eq!(
+            top,
+            vec![
+                ("Old".to_string(), 300),
+                ("New".to_string(), 250),
+                ("Old".to_string(), 250),
+                ("Old".to_string(), 200),
+                ("Old".to_string(), 100),
+            ]
+        );
+        assert_eq!(high_scores.top("./other.ron").len(), 1);
+    }
+
+    #[test]
+    fn test_difficulty() {
+        assert_eq!("Hard".parse::<Difficulty>(), Ok(Difficulty::Hard));
+        assert_eq!(
+            "custom:200,50,100,10".parse::<Difficulty>(),
+            Ok(Difficulty::Custom(DifficultySettings {
+                enemy_damage: 200,
+                enemy_health: 50,
+                healing: 100,
+                dodge: 10,
+            }))
+        );
+        assert!("custom:1,2".parse::<Difficulty>().is_err());
+        assert!("impossible".parse::<Difficulty>().is_err());
+
+        let mut world = World::new();
+        world.objects[LOC_TROLL].health = Some(Health(0));
+        world.set_difficulty(Difficulty::Hard);
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(150)));
+        assert_eq!(world.objects[LOC_TROLL].health, Some(Health(0)));
+        world.set_difficulty(Difficulty::Easy);
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(75)));
+
+        // Healing items heal more on easy
+        world.objects[LOC_PLAYER].health = Some(Health(50));
+        world.objects[LOC_PLAYER].location = Some(LOC_TAVERN);
+        let result = world.play(&Command::Get("apple".to_string()));
+        assert_eq!(
+            result,
+            "You have consumed the item. Your health has increased to 65\n"
+        );
+        assert!(world.display_summary().contains("Difficulty: Easy\n"));
+    }
+
+    #[test]
+    fn test_combat_rolls() {
+        let mut world = World::new();
+        world.objects[SWORD].accuracy = Some(80);
+        world.objects[SWORD].crit_chance = Some(10);
+        world.objects[SWORD].crit_multiplier = Some(250);
+        world.objects[LOC_BEAR].defence = Some(2);
+        world.objects[LOC_BEAR].accuracy = Some(70);
+
+        // A roll at or above the accuracy misses
+        let (log, damage) = world.resolve_player_attack(SWORD, LOC_BEAR, 80, 0);
+        assert_eq!(damage, 0);
+        assert!(log.contains("miss"));
+
+        // A hit, then a critical hit, both reduced by the bear's defence
+        let (_, damage) = world.resolve_player_attack(SWORD, LOC_BEAR, 10, 50);
+        assert_eq!(damage, 18);
+        let (log, damage) = world.resolve_player_attack(SWORD, LOC_BEAR, 10, 5);
+        assert_eq!(damage, 48);
+        assert!(log.contains("Critical hit!"));
+
+        // The bear misses, then the player's armour helps
+        let (_, damage) = world.resolve_enemy_attack(LOC_BEAR, 70, 99, 15);
+        assert_eq!(damage, 0);
+        let armour = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Shield".to_string()],
+            description: "A shield.".to_string(),
+            location: Some(LOC_PLAYER),
+            item: Some(true),
+            defence: Some(5),
+            dodge: Some(10),
+            ..Default::default()
+        });
+        assert_eq!(world.player_dodge(), 10);
+        let (_, damage) = world.resolve_enemy_attack(LOC_BEAR, 0, 5, 15);
+        assert_eq!(damage, 0);
+        let (log, damage) = world.resolve_enemy_attack(LOC_BEAR, 0, 50, 15);
+        assert_eq!(damage, 10);
+        assert_eq!(log, "\nYou got hit for 10 damage. Your armour absorbs 5");
+        assert_eq!(
+            world.player_defence(),
+            world.objects[armour].defence.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_status_effects() {
+        let mut world = World::new();
+        let health = world.objects[LOC_PLAYER].health.unwrap();
+
+        // Poison hurts every turn and wears off
+        let poison = EffectSpec {
+            kind: EffectKind::Poison,
+            turns: 2,
+            power: 4,
+            chance: None,
+        };
+        world.apply_effect(LOC_PLAYER, &poison);
+        assert_eq!(world.describe_effects(LOC_PLAYER), "poisoned (2 turns)");
+        let output = world.play(&Command::Drop("nothing".to_string()));
+        assert!(output.ends_with("You take 4 damage from poison.\n"));
+        // Looking around takes no time
+        world.play(&Command::Inventory);
+        assert_eq!(
+            world.objects[LOC_PLAYER].health,
+            Some(Health(health.value() - 4))
+        );
+        world.play(&Command::Drop("nothing".to_string()));
+        assert_eq!(
+            world.objects[LOC_PLAYER].health,
+            Some(Health(health.value() - 8))
+        );
+        assert!(world.effects.is_empty());
+
+        // Effects on an enemy wear down its health in the world
+        world.apply_effect(
+            LOC_BEAR,
+            &EffectSpec {
+                kind: EffectKind::Stun,
+                turns: 1,
+                power: 0,
+                chance: None,
+            },
+        );
+        world.apply_effect(LOC_BEAR, &poison);
+        assert!(world.is_stunned(LOC_BEAR));
+        world.objects[LOC_BEAR].health = Some(Health(50));
+        world.tick_effects();
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(46)));
+        assert!(!world.is_stunned(LOC_BEAR));
+        world.clear_effects(LOC_BEAR);
+        assert!(world.effects.is_empty());
+
+        // Regeneration doesn't heal an enemy past its maximum health
+        let max = world.enemy_max_health(LOC_BEAR);
+        world.objects[LOC_BEAR].health = Some(Health(max - 1));
+        world.apply_effect(
+            LOC_BEAR,
+            &EffectSpec {
+                kind: EffectKind::Regeneration,
+                turns: 1,
+                power: 20,
+                chance: None,
+            },
+        );
+        world.tick_effects();
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(max)));
+
+        // Strength adds to the player's damage
+        world.apply_effect(
+            LOC_PLAYER,
+            &EffectSpec {
+                kind: EffectKind::Strength,
+                turns: 3,
+                power: 10,
+                chance: None,
+            },
+        );
+        assert_eq!(world.effect_power(LOC_PLAYER, EffectKind::Strength), 10);
+    }
+
+    #[test]
+    fn test_ranged_attack() {
+        let mut world = World::new();
+        let troll = "Troll (enemy)".to_string();
+        world.objects[LOC_PLAYER].location = Some(LOC_TAVERN);
+
+        // A sword can't reach the troll in the dungeons next door
+        world.objects[SWORD].location = Some(LOC_PLAYER);
+        let output = world.do_attack(&troll);
+        assert!(output.contains("You need a ranged weapon"));
+
+        // The bow can, using up an arrow per shot
+        let arrows = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Arrows".to_string()],
+            description: "A quiver of arrows.".to_string(),
+            location: Some(LOC_PLAYER),
+            item: Some(true),
+            quantity: Some(1),
+            ..Default::default()
+        });
+        world.objects[BOW].location = Some(LOC_PLAYER);
+        world.objects[BOW].ranged = true;
+        world.objects[BOW].ammo = Some(arrows);
+        assert_eq!(world.ranged_weapon(), Some(BOW));
+        let output = world.do_attack(&troll);
+        assert!(output.ends_with("too far away to strike back.\n"));
+        assert_eq!(world.objects[LOC_TROLL].health, Some(Health(90)));
+        assert_eq!(world.objects[arrows].quantity, Some(0));
+        assert_eq!(world.objects[arrows].location, None);
+
+        let output = world.do_attack(&troll);
+        assert_eq!(output, "You have no arrows for the Bow!!\n");
+        assert_eq!(world.objects[LOC_TROLL].health, Some(Health(90)));
+    }
+
+    #[test]
+    fn test_durability() {
+        let mut world = World::new();
+        world.templates.insert(
+            "broken sword".to_string(),
+            Object {
+                label: vec!["Broken sword".to_string()],
+                description: "A broken sword.".to_string(),
+                item: Some(true),
+                attack: Some(5),
+                ..Default::default()
+            },
+        );
+        world.objects[SWORD].location = Some(LOC_PLAYER);
+        world.objects[SWORD].durability = Some(3);
+        world.objects[SWORD].max_durability = Some(10);
+        world.objects[SWORD].broken = Some("broken sword".to_string());
+        world.equipped = Some(SWORD);
+
+        // Worn weapons hit for half, then break into their broken version
+        assert_eq!(world.weapon_attack(SWORD), 20);
+        assert_eq!(world.wear(SWORD), "\nYour Sword is badly worn.");
+        assert_eq!(world.weapon_attack(SWORD), 10);
+        world.wear(SWORD);
+        let broken = world.objects.len();
+        assert!(world.wear(SWORD).starts_with("\nYour Sword breaks!"));
+        assert!(world.objects[SWORD].despawned);
+        assert_eq!(world.objects[broken].location, Some(LOC_PLAYER));
+        assert_eq!(world.equipped, None);
+
+        // Repairing with a repair item uses it up
+        let spear = 13;
+        world.objects[spear].location = Some(LOC_PLAYER);
+        world.objects[spear].durability = Some(2);
+        world.objects[spear].max_durability = Some(20);
+        let whetstone = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Whetstone".to_string()],
+            description: "A whetstone.".to_string(),
+            location: Some(LOC_PLAYER),
+            item: Some(true),
+            repair: Some(5),
+            ..Default::default()
+        });
+        let output = world.play(&parse("repair spear".to_string()));
+        assert_eq!(
+            output,
+            "You repair the Spear with the Whetstone. Durability: 7/20\n"
+        );
+        assert_eq!(world.objects[whetstone].location, None);
+        let output = world.do_repair(&"spear".to_string());
+        assert_eq!(output, "There is nothing here to repair it with.\n");
+
+        // A forge repairs fully
+        world.objects[LOC_FOREST].repairs = true;
+        world.do_repair(&"spear".to_string());
+        assert_eq!(world.objects[spear].durability, Some(20));
+    }
+
+    #[test]
+    fn test_crafting() {
+        let mut world = World::new();
+        world.templates.insert(
+            "bone spear".to_string(),
+            Object {
+                label: vec!["Bone spear".to_string()],
+                description: "A spear tipped with bone.".to_string(),
+                item: Some(true),
+                attack: Some(30),
+                ..Default::default()
+            },
+        );
+        world.recipes.push(Recipe {
+            ingredients: vec![12, 13],
+            result: "bone spear".to_string(),
+            known: false,
+        });
+        let bone_spear = world.objects.len();
+        assert_eq!(world.do_recipes(), "You don't know any recipes yet.\n");
+
+        // Crafting needs the ingredients in the inventory
+        world.objects[BONES].location = Some(LOC_PLAYER);
+        let output = world.play(&parse("combine bones with spear".to_string()));
+        assert_eq!(output, "You are not holding any spear.\n");
+
+        world.objects[SPEAR].location = Some(LOC_PLAYER);
+        let output = world.play(&parse("combine spear with bones".to_string()));
+        assert_eq!(
+            output,
+            "You combine the Bones and the Spear into a spear tipped with bone.\nYou learned a new recipe!\n"
+        );
+        assert_eq!(world.objects[BONES].location, None);
+        assert_eq!(world.objects[SPEAR].location, None);
+        assert_eq!(world.objects[bone_spear].location, Some(LOC_PLAYER));
+        assert_eq!(
+            world.do_recipes(),
+            "Known recipes:\nBone spear: Bones + Spear\n"
+        );
+        assert_eq!(
+            world.play(&parse("craft bone spear".to_string())),
+            "You still need: Bones, Spear\n"
+        );
+    }
+
+    #[test]
+    fn test_spawn_and_despawn() {
+        let mut world = World::new();
+        world.templates.insert(
+            "wolf".to_string(),
+            Object {
+                label: vec!["Wolf".to_string()],
+                description: "A grey wolf (enemy)".to_string(),
+                enemy: true,
+                health: Some(Health(40)),
+                attack: Some(10),
+                loot: vec!["pelt".to_string()],
+                ..Default::default()
+            },
+        );
+        world.templates.insert(
+            "pelt".to_string(),
+            Object {
+                label: vec!["Pelt".to_string()],
+                description: "A wolf pelt.".to_string(),
+                item: Some(true),
+                ..Default::default()
+            },
+        );
+        assert_eq!(world.spawn("dragon", Some(LOC_FOREST)), None);
+
+        // Spawned objects are added at the end and can be seen
+        let count = world.objects.len();
+        let wolf = world.spawn("wolf", Some(LOC_FOREST)).unwrap();
+        assert_eq!(wolf, count);
+        assert_eq!(world.objects[wolf].template, Some("wolf".to_string()));
+        assert!(world.do_look("").contains("A grey wolf (enemy)"));
+
+        // Loot is spawned when the enemy dies
+        world.objects[wolf].health = Some(Health(0));
+        let output = world.drop_loot(wolf);
+        assert_eq!(output, "The Wolf dropped A wolf pelt.\n");
+        let pelt = wolf + 1;
+        assert_eq!(world.objects[pelt].location, Some(LOC_FOREST));
+
+        // Despawning keeps every index in place, along with what the object held
+        world.objects[SWORD].location = Some(wolf);
+        world.despawn(wolf);
+        assert_eq!(world.objects.len(), count + 2);
+        assert!(world.objects[wolf].despawned);
+        assert!(world.objects[SWORD].despawned);
+        assert_eq!(world.objects[pelt].location, Some(LOC_FOREST));
+        assert_eq!(
+            world.object_index("wolf", Some(LOC_PLAYER), Distance::NotHere),
+            AmbiguousOption::None
+        );
+    }
+
+    #[test]
+    fn test_enemy_recovery() {
+        let mut world = World::new();
+
+        // Regeneration up to full health, a turn at a time
+        world.objects[LOC_BEAR].health = Some(Health(95));
+        world.objects[LOC_BEAR].regen = Some(3);
+        world.play(&Command::Drop("nothing".to_string()));
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(98)));
+        world.play(&Command::Drop("nothing".to_string()));
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(100)));
+
+        // Dead enemies come back after their respawn time
+        world.objects[LOC_TROLL].health = Some(Health(0));
+        world.objects[LOC_TROLL].died_at = Some(world.turn);
+        world.objects[LOC_TROLL].respawn = Some(2);
+        world.objects[LOC_PLAYER].location = Some(LOC_DUNGEONS);
+        assert_eq!(
+            world
+                .play(&Command::Drop("nothing".to_string()))
+                .find("back!"),
+            None
+        );
+        let output = world.play(&Command::Drop("nothing".to_string()));
+        assert!(output.ends_with("The Troll (enemy) is back!\n"));
+        assert_eq!(world.objects[LOC_TROLL].health, Some(Health(100)));
+
+        // What running away does to the enemy's health. By default it keeps its wounds.
+        world.objects[LOC_BANDITS].health = Some(Health(40));
+        world.apply_flee(LOC_BANDITS);
+        assert_eq!(world.objects[LOC_BANDITS].health, Some(Health(40)));
+        world.objects[LOC_BANDITS].on_flee = FleeBehaviour::PartialHeal(50);
+        world.apply_flee(LOC_BANDITS);
+        assert_eq!(world.objects[LOC_BANDITS].health, Some(Health(70)));
+        world.objects[LOC_BANDITS].on_flee = FleeBehaviour::FullHeal;
+        world.apply_flee(LOC_BANDITS);
+        assert_eq!(world.objects[LOC_BANDITS].health, Some(Health(100)));
+
+        // Only the first death of an enemy counts for the score and drops loot
+        world.templates.insert(
+            "pelt".to_string(),
+            Object {
+                label: vec!["Pelt".to_string()],
+                description: "A bear pelt.".to_string(),
+                item: Some(true),
+                ..Default::default()
+            },
+        );
+        world.objects[LOC_BEAR].loot = vec!["pelt".to_string()];
+        world.objects[LOC_BEAR].accuracy = Some(0);
+        world.objects[LOC_PLAYER].location = Some(LOC_CAVE);
+        world.objects[SWORD].location = Some(LOC_PLAYER);
+        let count = world.objects.len();
+        for _ in 0..2 {
+            world.objects[LOC_BEAR].health = Some(Health(1));
+            world.play(&Command::Attack("bear".to_string()));
+            world.play(&Command::Use("sword".to_string()));
+            assert!(world.health(LOC_BEAR).is_dead());
+            assert_eq!(world.stats.enemies_defeated, 1);
+            assert_eq!(world.objects.len(), count + 1);
+        }
+    }
+
+    #[test]
+    fn test_enemy_damage_persists() {
+        let mut world = World::new();
+        world.objects[LOC_PLAYER].location = Some(LOC_CAVE);
+        world.objects[SWORD].location = Some(LOC_PLAYER);
+        world.objects[LOC_BEAR].accuracy = Some(0);
+
+        // Each blow is written to the world, so it shows up everywhere
+        world.play(&Command::Attack("bear".to_string()));
+        assert_eq!(world.mode, Mode::Fighting(LOC_BEAR));
+        world.play(&Command::Use("sword".to_string()));
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(80)));
+        world.play(&Command::Run);
+        assert_eq!(world.mode, Mode::Exploring);
+        assert!(world.do_look("bear").contains("Health: 80"));
+
+        // and it is kept in a saved game
+        let file = std::env::temp_dir().join("rust_in_peace_damage_test.ron");
+        let file = file.to_str().unwrap().to_string();
+        world.do_save(&file);
+        let mut loaded = World::new();
+        loaded.do_load(&file);
+        assert_eq!(loaded.objects[LOC_BEAR].health, Some(Health(80)));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn test_events() {
+        let mut world = World::new();
+
+        // Commands report what happened as events
+        let events = world.update_state(&Command::Go("north".to_string()));
+        assert_eq!(
+            events[0],
+            Event::Moved {
+                location: LOC_TAVERN,
+                first_visit: true
+            }
+        );
+        assert!(world
+            .render(&events)
+            .starts_with("OK.\n You are in the Tavern"));
+
+        // Fights take their own commands until someone wins or the player runs
+        world.objects[LOC_PLAYER].location = Some(LOC_CAVE);
+        world.objects[SWORD].location = Some(LOC_PLAYER);
+        world.objects[LOC_BEAR].health = Some(Health(20));
+        assert_eq!(
+            world.update_state(&parse("attack bear".to_string())),
+            vec![Event::CombatStarted(LOC_BEAR)]
+        );
+        assert_eq!(world.play(&parse("look".to_string())), FIGHT_HINT);
+        let events = world.update_state(&parse("use sword".to_string()));
+        assert!(events.contains(&Event::Damaged {
+            target: LOC_BEAR,
+            amount: 20,
+            health_left: 0
+        }));
+        assert!(events.contains(&Event::EnemyKilled(LOC_BEAR)));
+        assert_eq!(world.mode, Mode::Exploring);
+
+        // and so do conversations
+        let hermit = world.objects.len();
+        world.objects.push(Object {
+            label: vec!["Hermit".to_string()],
+            description: "An old hermit".to_string(),
+            location: Some(LOC_CAVE),
+            npc: true,
+            dialogue: Some(vec![DialogueNode {
+                text: "Hello there.".to_string(),
+                options: vec![DialogueOption {
+                    text: "Goodbye.".to_string(),
+                    ..Default::default()
+                }],
+            }]),
+            ..Default::default()
+        });
+        assert_eq!(
+            world.update_state(&parse("talk to hermit".to_string())),
+            vec![Event::Dialogue {
+                npc: hermit,
+                text: "Hello there.".to_string(),
+                options: vec!["Goodbye.".to_string()]
+            }]
+        );
+        assert_eq!(world.play(&parse("inventory".to_string())), TALK_HINT);
+        assert_eq!(
+            world.update_state(&parse("1".to_string())),
+            vec![Event::ConversationEnded(hermit)]
+        );
+        assert_eq!(world.mode, Mode::Exploring);
+    }
+
+    #[test]
+    fn test_localization() {
+        // Placeholders are filled in order, or by number when a translation reorders them
+        let mut catalog = Catalog::default();
+        assert_eq!(
+            catalog.translate("You pick up the {}.\n", &[&"Sword"]),
+            "You pick up the Sword.\n"
+        );
+        catalog.messages.insert(
+            "The {} has {} gold.".to_string(),
+            "{1} gold belong to the {0}.".to_string(),
+        );
+        assert_eq!(
+            catalog.translate("The {} has {} gold.", &[&"Innkeeper", &12]),
+            "12 gold belong to the Innkeeper."
+        );
+
+        // The German catalog translates messages and the words the player types
+        let mut world = World::new();
+        world.catalog = Catalog::load("de").unwrap();
+        assert_eq!(
+            world.play(&Command::Look("trees".to_string())),
+            "Ungültiger Befehl!!\n"
+        );
+        assert!(world
+            .play(&parse(world.catalog.to_english("Geh Norden")))
+            .contains("Du bist hier: Tavern"));
+        assert_eq!(
+            world.catalog.to_english("Sprich mit dem Wirt"),
+            "talk to dem wirt"
+        );
+        assert_eq!(
+            world.catalog.to_english("kombiniere Knochen mit Speer"),
+            "combine knochen with speer"
+        );
+        // Command words only count at the start of a command, so names stay as they are
+        assert_eq!(
+            world
+                .catalog
+                .to_english("nimm Karte, Karte dann geh Norden"),
+            "get karte , map then go north"
+        );
+        assert_eq!(world.catalog.to_english("Hilfe nimm"), "help get");
+
+        // Parse errors are catalog messages, so they can be translated too
+        let parse_err = "unmöglich".parse::<Difficulty>().unwrap_err();
+        assert_eq!(
+            world.catalog.translate(&parse_err, &[&"unmöglich"]),
+            "Unbekannte Schwierigkeit 'unmöglich'"
+        );
+
+        // Every translation has the same placeholders as the English text
+        let placeholders = |text: &str| text.matches('{').count();
+        for (english, german) in &world.catalog.messages {
+            assert_eq!(placeholders(english), placeholders(german), "{}", english);
+        }
+    }
+
+    #[test]
+    fn test_health() {
+        // Damage and healing stop at zero and at the maximum
+        let mut health = Health(20);
+        assert_eq!(health.damage(25), 20);
+        assert!(health.is_dead());
+        assert_eq!(health.heal(u64::MAX, MAX_HEALTH), MAX_HEALTH);
+        assert_eq!(health, Health(MAX_HEALTH));
+        assert_eq!(Health(80).scaled(150), Health(120));
+        assert_eq!(Health(u64::MAX).scaled(150).value(), u64::MAX / 100);
+
+        // The spear does more damage than a weak enemy has left
+        let mut world = World::new();
+        world.objects[LOC_BEAR].health = Some(Health(20));
+        assert_eq!(world.damage(LOC_BEAR, 25), 20);
+        assert_eq!(world.objects[LOC_BEAR].health, Some(Health(0)));
+        world.objects[LOC_TROLL].health = Some(Health(0));
+        world.objects[LOC_BANDITS].health = Some(Health(0));
+        assert!(world.game_over());
+
+        // Huge healing items can't push the player over the maximum, and nothing can crash
+        world.objects[LOC_PLAYER].health = Some(Health(99));
+        world.objects[APPLE].health = Some(Health(u64::MAX));
+        world.do_consume(Some(APPLE));
+        assert_eq!(world.health(LOC_PLAYER), Health(MAX_HEALTH));
+        assert_eq!(world.do_consume(None), "You cannot consume that!!\n");
+    }
+
+    #[test]
+    fn test_help() {
+        let mut world = World::new();
+
+        // Help only lists what can be done right now
+        let help = world.play(&parse("help".to_string()));
+        assert!(help.contains("attack <enemy>"));
+        assert!(!help.contains("use <weapon>"));
+        world.mode = Mode::Fighting(LOC_BEAR);
+        let help = world.play(&parse("help".to_string()));
+        assert!(help.contains("use <weapon>"));
+        assert!(!help.contains("buy <item>"));
+        world.mode = Mode::Exploring;
+
+        // Each command can be explained on its own
+        let help = world.play(&parse("help get".to_string()));
+        assert!(help.contains("get <item>"));
+        assert!(help.contains("Examples:"));
+        assert!(world
+            .play(&parse("help go to".to_string()))
+            .contains("go to <place>"));
+        assert!(world
+            .play(&parse("help fly".to_string()))
+            .starts_with("There is no command called 'fly'."));
+
+        // Typos get a suggestion
+        let output = world.play(&parse("atack bear".to_string()));
+        assert!(output.starts_with("Invalid command!!\n"));
+        assert!(output.contains("Did you mean 'attack'?"));
+        assert_eq!(edit_distance("atack", "attack"), 1);
+    }
+
+    #[test]
+    fn test_fuzzy_object_names() {
+        let mut world = World::new();
+        world.objects[LOC_PLAYER].location = Some(LOC_DUNGEONS);
+        let find = |world: &World, noun: &str| {
+            world.object_index(noun, Some(LOC_PLAYER), Distance::NotHere)
+        };
+
+        // Single words, adjectives from the description, starts of words and small typos
+        assert_eq!(find(&world, "troll"), AmbiguousOption::Some(LOC_TROLL));
+        assert_eq!(find(&world, "rusty sword"), AmbiguousOption::Some(SWORD));
+        assert_eq!(find(&world, "swo"), AmbiguousOption::Some(SWORD));
+        assert_eq!(find(&world, "swrod"), AmbiguousOption::Some(SWORD));
+        assert_eq!(find(&world, "sw"), AmbiguousOption::None);
+        assert_eq!(find(&world, "shiny sword"), AmbiguousOption::None);
+        assert_eq!(find(&world, "rusty"), AmbiguousOption::None);
+
+        // Equally good matches are still ambiguous, but a better one wins
+        assert_eq!(find(&world, "enemy"), AmbiguousOption::Ambiguous);
+        world.objects[BONES].label = vec!["Swords".to_string()];
+        assert_eq!(find(&world, "sword"), AmbiguousOption::Some(SWORD));
+        assert_eq!(find(&world, "swor"), AmbiguousOption::Ambiguous);
+
+        world.play(&parse("get swrod".to_string()));
+        assert_eq!(world.objects[SWORD].location, Some(LOC_PLAYER));
+
+        // Directions are never guessed from a typo, so "west" can't mean east even in a
+        // world without any west exits
+        for object in world.objects.iter_mut() {
+            object.label.retain(|label| label != "West");
+        }
+        world.objects[LOC_PLAYER].location = Some(LOC_VILLAGE);
+        assert_eq!(find(&world, "west"), AmbiguousOption::None);
+        assert_eq!(find(&world, "eats"), AmbiguousOption::None);
+        world.play(&parse("go west".to_string()));
+        assert_eq!(world.objects[LOC_PLAYER].location, Some(LOC_VILLAGE));
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut world = World::new();
+        world.objects[LOC_PLAYER].location = Some(LOC_DUNGEONS);
+
+        // Command words of the current mode and the names of what the player can see
+        let completions = world.completions();
+        assert_eq!(completions.complete("att"), vec!["attack"]);
+        assert_eq!(completions.complete("Attack tr"), vec!["attack troll"]);
+        assert!(completions
+            .complete("get s")
+            .contains(&"get sword".to_string()));
+        assert!(completions.complete("go ").contains(&"go to".to_string()));
+        assert!(completions.complete("get b").is_empty());
+
+        // Names also follow the words the parser skips, like "at" in "look at"
+        assert_eq!(completions.complete("look at tr"), vec!["look at troll"]);
+        assert_eq!(completions.complete("pick up sw"), vec!["pick up sword"]);
+        assert!(completions
+            .complete("talk ")
+            .contains(&"talk to".to_string()));
+        assert_eq!(
+            parse("pick up sword".to_string()),
+            Command::Get("sword".to_string())
+        );
+        assert!(!completions.labels.contains(&"player".to_string()));
+
+        world.mode = Mode::Fighting(LOC_TROLL);
+        assert_eq!(world.completions().complete("u"), vec!["use"]);
+
+        // Names also follow "with"
+        let completions = Completions {
+            verbs: vec!["combine".to_string()],
+            labels: vec!["bones".to_string(), "spear".to_string()],
+        };
+        assert_eq!(
+            completions.complete("combine bones with sp"),
+            vec!["combine bones with spear"]
+        );
+    }
+
+    #[test]
+    fn test_chaining() {
+        assert_eq!(
+            parse("get sword, go west then look".to_string()),
+            Command::Chain(vec![
+                Command::Get("sword".to_string()),
+                Command::Go("west".to_string()),
+                Command::Look("".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse("use sword x3".to_string()),
+            Command::Chain(vec![Command::Use("sword".to_string()); 3])
+        );
+        assert_eq!(
+            parse("use sword x500".to_string()),
+            Command::Chain(vec![Command::Use("sword".to_string()); MAX_REPEAT])
+        );
+        assert_eq!(parse("g".to_string()), Command::Again);
+        // "then" inside a name doesn't split the command
+        assert_eq!(
+            parse("look at then and now then get sword".to_string()),
+            Command::Chain(vec![
+                Command::Look("then and now".to_string()),
+                Command::Get("sword".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse("get then".to_string()),
+            Command::Get("then".to_string())
+        );
+
+        // A chain stops at the first command that doesn't work
+        let mut world = World::new();
+        world.objects[LOC_PLAYER].location = Some(LOC_DUNGEONS);
+        assert_eq!(
+            world.update_state(&Command::Again),
+            vec![Event::Failed("There is nothing to do again.\n".to_string())]
+        );
+        let events = world.update_state(&parse("get sword; go nowhere; look".to_string()));
+        assert_eq!(world.objects[SWORD].location, Some(LOC_PLAYER));
+        assert!(matches!(events.last(), Some(Event::Failed(_))));
+        let look = world.play(&parse("look".to_string()));
+        assert_eq!(world.play(&parse("again".to_string())), look);
+
+        // Repeating a chain that starts with "again" doesn't repeat the chain inside itself
+        world.play(&parse("again, look".to_string()));
+        assert_eq!(world.play(&parse("again".to_string())), look.repeat(2));
+
+        // Repeated blows stop once the enemy is dead
+        world.objects[LOC_PLAYER].location = Some(LOC_CAVE);
+        world.objects[LOC_BEAR].health = Some(Health(20));
+        let events = world.update_state(&parse("attack bear then use sword x3".to_string()));
+        assert!(events.contains(&Event::EnemyKilled(LOC_BEAR)));
+        assert!(!events.iter().any(|event| matches!(event, Event::Failed(_))));
+        assert_eq!(world.mode, Mode::Exploring);
 
-        // Test the display of available locations
-        let expected_result = "Available locations:\n0: Forest\n1: Dungeons\n2: Cave\n3: Tavern\n4: Village\n5: Stronghold\n";
-        let result = world.display_locations();
-        assert_eq!(result, expected_result);
+        // Quitting ends the chain too
+        let events = world.update_state(&parse("look, quit, look".to_string()));
+        assert_eq!(events.last(), Some(&Event::Quit));
     }
 }